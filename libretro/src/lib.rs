@@ -0,0 +1,531 @@
+// libretro core for chippy, so it can be loaded by RetroArch and other libretro frontends.
+// Based on the API in https://github.com/libretro/libretro-common/blob/master/include/libretro.h
+
+// The unsafe entry points are only called by libretro frontends, which follow the pointer rules in libretro.h
+#![allow(clippy::missing_safety_doc)]
+
+use chippy::core::{Chip8, FontSet, Palette, Target, Timing};
+use chippy::{Core, ValueEnum};
+use std::ffi::{c_char, c_uint, c_void, CStr};
+use std::sync::Mutex;
+
+const RETRO_API_VERSION: c_uint = 1;
+
+const RETRO_ENVIRONMENT_SET_PIXEL_FORMAT: c_uint = 10;
+const RETRO_ENVIRONMENT_GET_VARIABLE: c_uint = 15;
+const RETRO_ENVIRONMENT_SET_VARIABLES: c_uint = 16;
+const RETRO_ENVIRONMENT_GET_VARIABLE_UPDATE: c_uint = 17;
+const RETRO_ENVIRONMENT_SET_SYSTEM_AV_INFO: c_uint = 32;
+
+const RETRO_PIXEL_FORMAT_XRGB8888: c_uint = 1;
+
+const RETRO_DEVICE_JOYPAD: c_uint = 1;
+const RETRO_DEVICE_KEYBOARD: c_uint = 3;
+
+const RETRO_REGION_NTSC: c_uint = 0;
+
+const SAMPLE_RATE: f64 = 44100.0;
+
+// Keyboard layout matching the native frontend (QWERTY)
+//     1 2 3 4        1 2 3 C
+//     Q W E R   ->   4 5 6 D
+//     A S D F        7 8 9 E
+//     Z X C V        A 0 B F
+// RETROK_* values are the lowercase ASCII codes of the keys.
+const KEYBOARD_MAP: [u8; 16] = [
+    b'x', b'1', b'2', b'3',
+    b'q', b'w', b'e', b'a',
+    b's', b'd', b'z', b'c',
+    b'4', b'r', b'f', b'v'
+];
+
+// The second CHIP-8X keypad, laid out the same way on the right side of the keyboard
+//     7 8 9 0
+//     U I O P
+//     J K L ;
+//     M , . /
+const KEYBOARD_MAP_2: [u8; 16] = [
+    b'm', b'7', b'8', b'9',
+    b'u', b'i', b'o', b'j',
+    b'k', b'l', b',', b'.',
+    b'0', b'p', b';', b'/'
+];
+
+// RETRO_DEVICE_ID_JOYPAD_* to CHIP-8 key. The D-pad uses the common 5/7/8/9 movement layout.
+const JOYPAD_MAP: [(c_uint, usize); 12] = [
+    (0, 0x4), // B
+    (1, 0x2), // Y
+    (2, 0xF), // Select
+    (3, 0x0), // Start
+    (4, 0x5), // Up
+    (5, 0x8), // Down
+    (6, 0x7), // Left
+    (7, 0x9), // Right
+    (8, 0x6), // A
+    (9, 0x1), // X
+    (10, 0xA), // L
+    (11, 0xB) // R
+];
+
+// Core options, the first value of each is the default
+const OPTION_TARGET: &CStr = c"chippy_target";
+const OPTION_CLOCK: &CStr = c"chippy_clock";
+const OPTION_TIMING: &CStr = c"chippy_timing";
+const OPTION_PALETTE: &CStr = c"chippy_palette";
+const OPTION_FONT: &CStr = c"chippy_font";
+const OPTION_VOLUME: &CStr = c"chippy_volume";
+const OPTION_LOW_PASS: &CStr = c"chippy_low_pass";
+
+#[repr(C)]
+pub struct RetroSystemInfo {
+    library_name: *const c_char,
+    library_version: *const c_char,
+    valid_extensions: *const c_char,
+    need_fullpath: bool,
+    block_extract: bool
+}
+
+#[repr(C)]
+pub struct RetroGameGeometry {
+    base_width: c_uint,
+    base_height: c_uint,
+    max_width: c_uint,
+    max_height: c_uint,
+    aspect_ratio: f32
+}
+
+#[repr(C)]
+pub struct RetroSystemTiming {
+    fps: f64,
+    sample_rate: f64
+}
+
+#[repr(C)]
+pub struct RetroSystemAvInfo {
+    geometry: RetroGameGeometry,
+    timing: RetroSystemTiming
+}
+
+#[repr(C)]
+pub struct RetroGameInfo {
+    path: *const c_char,
+    data: *const c_void,
+    size: usize,
+    meta: *const c_char
+}
+
+#[repr(C)]
+struct RetroVariable {
+    key: *const c_char,
+    value: *const c_char
+}
+
+type EnvironmentFn = extern "C" fn(cmd: c_uint, data: *mut c_void) -> bool;
+type VideoRefreshFn = extern "C" fn(data: *const c_void, width: c_uint, height: c_uint, pitch: usize);
+type AudioSampleFn = extern "C" fn(left: i16, right: i16);
+type AudioSampleBatchFn = extern "C" fn(data: *const i16, frames: usize) -> usize;
+type InputPollFn = extern "C" fn();
+type InputStateFn = extern "C" fn(port: c_uint, device: c_uint, index: c_uint, id: c_uint) -> i16;
+
+// Callbacks handed to us by the frontend
+struct Callbacks {
+    environment: Option<EnvironmentFn>,
+    video_refresh: Option<VideoRefreshFn>,
+    audio_sample_batch: Option<AudioSampleBatchFn>,
+    input_poll: Option<InputPollFn>,
+    input_state: Option<InputStateFn>
+}
+
+// Settings from the core options
+struct Options {
+    target: Target,
+    clock: u32,
+    timing: Timing,
+    palette: Palette,
+    // None for the target's own font
+    font: Option<FontSet>,
+    volume: f32,
+    low_pass: f32
+}
+
+struct Retro {
+    chip8: Chip8,
+    target: Target,
+    frame: Vec<u8>,
+    samples: Vec<i16>
+}
+
+static CALLBACKS: Mutex<Callbacks> = Mutex::new(Callbacks {
+    environment: None,
+    video_refresh: None,
+    audio_sample_batch: None,
+    input_poll: None,
+    input_state: None
+});
+
+static RETRO: Mutex<Option<Retro>> = Mutex::new(None);
+
+fn environment(cmd: c_uint, data: *mut c_void) -> bool {
+    match CALLBACKS.lock().unwrap().environment {
+        Some(callback) => callback(cmd, data),
+        None => false
+    }
+}
+
+// Read a core option's current value from the frontend
+fn get_variable(key: &CStr) -> Option<String> {
+    let mut variable = RetroVariable {
+        key: key.as_ptr(),
+        value: std::ptr::null()
+    };
+    if !environment(RETRO_ENVIRONMENT_GET_VARIABLE, &mut variable as *mut RetroVariable as *mut c_void) || variable.value.is_null() {
+        return None;
+    }
+    Some(unsafe { CStr::from_ptr(variable.value) }.to_string_lossy().into_owned())
+}
+
+fn get_options() -> Options {
+    let target = get_variable(OPTION_TARGET)
+        .and_then(|value| Target::from_str(&value, true).ok())
+        .unwrap_or_default();
+    let clock = get_variable(OPTION_CLOCK)
+        .and_then(|value| value.parse::<u32>().ok())
+        .unwrap_or(target.default_clock());
+    let timing = get_variable(OPTION_TIMING)
+        .and_then(|value| Timing::from_str(&value, true).ok())
+        .unwrap_or_default();
+    let palette = get_variable(OPTION_PALETTE)
+        .and_then(|value| Palette::from_str(&value, true).ok())
+        .unwrap_or_default();
+    let font = get_variable(OPTION_FONT)
+        .and_then(|value| FontSet::from_str(&value, true).ok());
+    // The volume is a percentage like "100%", and the low-pass cutoff is in Hz or "off"
+    let volume = get_variable(OPTION_VOLUME)
+        .and_then(|value| value.trim_end_matches('%').parse::<f32>().ok())
+        .map_or(1.0, |percent| percent / 100.0);
+    let low_pass = get_variable(OPTION_LOW_PASS)
+        .and_then(|value| value.parse::<f32>().ok())
+        .unwrap_or(0.0);
+    Options {
+        target,
+        clock,
+        timing,
+        palette,
+        font,
+        volume,
+        low_pass
+    }
+}
+
+fn apply_options(retro: &mut Retro) {
+    let options = get_options();
+    let target_changed = options.target != retro.target;
+    retro.target = options.target.clone();
+    retro.chip8.set_target(options.target);
+    retro.chip8.set_clock(options.clock);
+    retro.chip8.set_timing(options.timing);
+    retro.chip8.set_palette(options.palette.colors());
+    match options.font {
+        Some(font) => retro.chip8.set_font(&font.bytes()),
+        None => retro.chip8.set_default_font()
+    }
+    retro.chip8.set_volume(options.volume);
+    retro.chip8.set_low_pass(options.low_pass);
+
+    // A different target is a different machine, so the game starts over, and the frontend is told about the new
+    // display size and frame rate
+    if target_changed {
+        retro.chip8.reset();
+        let mut info = av_info(Some(&retro.chip8));
+        environment(RETRO_ENVIRONMENT_SET_SYSTEM_AV_INFO, &mut info as *mut RetroSystemAvInfo as *mut c_void);
+    }
+}
+
+fn av_info(chip8: Option<&Chip8>) -> RetroSystemAvInfo {
+    let (width, height, frame_rate) = match chip8 {
+        Some(chip8) => (chip8.get_width(), chip8.get_height(), chip8.get_frame_rate()),
+        None => (128, 64, 60.0)
+    };
+    // MEGA-CHIP is the biggest display, so frontends can switch targets without reallocating
+    let (max_width, max_height) = (256, 192);
+    RetroSystemAvInfo {
+        geometry: RetroGameGeometry {
+            base_width: width as c_uint,
+            base_height: height as c_uint,
+            max_width: max_width as c_uint,
+            max_height: max_height as c_uint,
+            aspect_ratio: width as f32 / height as f32
+        },
+        timing: RetroSystemTiming {
+            fps: frame_rate as f64,
+            sample_rate: SAMPLE_RATE
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_api_version() -> c_uint {
+    RETRO_API_VERSION
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_environment(callback: EnvironmentFn) {
+    CALLBACKS.lock().unwrap().environment = Some(callback);
+
+    // "default" for the clock and font means the recommended clock and the original font for the selected target
+    let variables = [
+        RetroVariable {
+            key: OPTION_TARGET.as_ptr(),
+            value: c"Target; xo|chip|super-modern|super-legacy|mega-chip|chip-8x|hires-chip|chip-48|super-1.0|eti-660|dream-6800".as_ptr()
+        },
+        RetroVariable {
+            key: OPTION_CLOCK.as_ptr(),
+            value: c"Instructions per frame; default|7|11|15|20|30|50|100|200|500|1000|2000|5000|10000|30000".as_ptr()
+        },
+        RetroVariable {
+            key: OPTION_TIMING.as_ptr(),
+            value: c"Timing; instructions|vip".as_ptr()
+        },
+        RetroVariable {
+            key: OPTION_PALETTE.as_ptr(),
+            value: c"Palette; octo|grayscale|green|amber".as_ptr()
+        },
+        RetroVariable {
+            key: OPTION_FONT.as_ptr(),
+            value: c"Font; default|octo|vip|eti-660|dream-6800|fish-n-chips|schip-1.1".as_ptr()
+        },
+        RetroVariable {
+            key: OPTION_VOLUME.as_ptr(),
+            value: c"Volume; 100%|0%|25%|50%|75%|125%|150%|200%".as_ptr()
+        },
+        RetroVariable {
+            key: OPTION_LOW_PASS.as_ptr(),
+            value: c"Low-pass filter (Hz); off|500|1000|2000|4000|8000".as_ptr()
+        },
+        RetroVariable {
+            key: std::ptr::null(),
+            value: std::ptr::null()
+        }
+    ];
+    callback(RETRO_ENVIRONMENT_SET_VARIABLES, variables.as_ptr() as *mut c_void);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_video_refresh(callback: VideoRefreshFn) {
+    CALLBACKS.lock().unwrap().video_refresh = Some(callback);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_audio_sample(_callback: AudioSampleFn) {
+    // Audio is always sent in batches
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_audio_sample_batch(callback: AudioSampleBatchFn) {
+    CALLBACKS.lock().unwrap().audio_sample_batch = Some(callback);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_input_poll(callback: InputPollFn) {
+    CALLBACKS.lock().unwrap().input_poll = Some(callback);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_input_state(callback: InputStateFn) {
+    CALLBACKS.lock().unwrap().input_state = Some(callback);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_init() {}
+
+#[no_mangle]
+pub extern "C" fn retro_deinit() {
+    *RETRO.lock().unwrap() = None;
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn retro_get_system_info(info: *mut RetroSystemInfo) {
+    *info = RetroSystemInfo {
+        library_name: c"chippy".as_ptr(),
+        library_version: c"0.1.0".as_ptr(),
+        valid_extensions: c"ch8|sc8|xo8|c8|mc8|c8x|c8h".as_ptr(),
+        need_fullpath: false,
+        block_extract: false
+    };
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn retro_get_system_av_info(info: *mut RetroSystemAvInfo) {
+    *info = av_info(RETRO.lock().unwrap().as_ref().map(|retro| &retro.chip8));
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_controller_port_device(_port: c_uint, _device: c_uint) {}
+
+#[no_mangle]
+pub extern "C" fn retro_reset() {
+    if let Some(retro) = RETRO.lock().unwrap().as_mut() {
+        retro.chip8.reset();
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_run() {
+    let callbacks = {
+        let callbacks = CALLBACKS.lock().unwrap();
+        (callbacks.video_refresh, callbacks.audio_sample_batch, callbacks.input_poll, callbacks.input_state)
+    };
+    let (video_refresh, audio_sample_batch, input_poll, input_state) = callbacks;
+
+    let mut updated = false;
+    environment(RETRO_ENVIRONMENT_GET_VARIABLE_UPDATE, &mut updated as *mut bool as *mut c_void);
+
+    let mut guard = RETRO.lock().unwrap();
+    let Some(retro) = guard.as_mut() else {
+        return;
+    };
+    if updated {
+        apply_options(retro);
+    }
+
+    // Input, only forward changes so the core's previous/current key states stay meaningful for Fx0A
+    if let (Some(input_poll), Some(input_state)) = (input_poll, input_state) {
+        input_poll();
+        // Keys 0x10-0x1F are the second CHIP-8X keypad, which is also mapped to the second joypad
+        let mut pressed = [false; 32];
+        for (key, retrok) in KEYBOARD_MAP.iter().chain(KEYBOARD_MAP_2.iter()).enumerate() {
+            pressed[key] |= input_state(0, RETRO_DEVICE_KEYBOARD, 0, *retrok as c_uint) != 0;
+        }
+        for port in 0 .. 2 {
+            for (id, key) in JOYPAD_MAP {
+                pressed[port as usize * 0x10 + key] |= input_state(port, RETRO_DEVICE_JOYPAD, 0, id) != 0;
+            }
+        }
+        for (key, pressed) in pressed.into_iter().enumerate() {
+            if pressed && !retro.chip8.get_key_pressed(key) {
+                retro.chip8.press_key(key);
+            } else if !pressed && retro.chip8.get_key_pressed(key) {
+                retro.chip8.release_key(key);
+            }
+        }
+    }
+
+    retro.chip8.run_frame();
+
+    // Video, the core draws RGBA but libretro wants XRGB8888 in native endianness
+    let width = retro.chip8.get_width();
+    let height = retro.chip8.get_height();
+    retro.frame.resize(width * height * 4, 0);
+    retro.chip8.draw(&mut retro.frame);
+    for pixel in retro.frame.chunks_exact_mut(4) {
+        let xrgb = u32::from_be_bytes([0, pixel[0], pixel[1], pixel[2]]);
+        pixel.copy_from_slice(&xrgb.to_ne_bytes());
+    }
+    if let Some(video_refresh) = video_refresh {
+        video_refresh(retro.frame.as_ptr() as *const c_void, width as c_uint, height as c_uint, width * 4);
+    }
+
+    // Audio, the instructions don't always add up to a whole frame of samples, so the core runs the audio on to fill it.
+    // Anything past the frame stays queued for the next one.
+    let frames = (SAMPLE_RATE / retro.chip8.get_frame_rate() as f64).ceil() as usize;
+    retro.chip8.queue_samples(frames);
+    retro.samples.clear();
+    for _i in 0 .. frames * 2 {
+        retro.samples.push((retro.chip8.get_sample().clamp(-1.0, 1.0) * i16::MAX as f32) as i16);
+    }
+    if let Some(audio_sample_batch) = audio_sample_batch {
+        audio_sample_batch(retro.samples.as_ptr(), retro.samples.len() / 2);
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_serialize_size() -> usize {
+    match RETRO.lock().unwrap().as_ref() {
+        Some(retro) => retro.chip8.save_state().len(),
+        None => 0
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn retro_serialize(data: *mut c_void, size: usize) -> bool {
+    let Some(state) = RETRO.lock().unwrap().as_ref().map(|retro| retro.chip8.save_state()) else {
+        return false;
+    };
+    if size < state.len() {
+        return false;
+    }
+    std::ptr::copy_nonoverlapping(state.as_ptr(), data as *mut u8, state.len());
+    true
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn retro_unserialize(data: *const c_void, size: usize) -> bool {
+    match RETRO.lock().unwrap().as_mut() {
+        Some(retro) => retro.chip8.load_state(std::slice::from_raw_parts(data as *const u8, size)),
+        None => false
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_cheat_reset() {}
+
+#[no_mangle]
+pub extern "C" fn retro_cheat_set(_index: c_uint, _enabled: bool, _code: *const c_char) {}
+
+#[no_mangle]
+pub unsafe extern "C" fn retro_load_game(game: *const RetroGameInfo) -> bool {
+    if game.is_null() || (*game).data.is_null() {
+        return false;
+    }
+    let rom = std::slice::from_raw_parts((*game).data as *const u8, (*game).size).to_vec();
+
+    let mut format = RETRO_PIXEL_FORMAT_XRGB8888;
+    if !environment(RETRO_ENVIRONMENT_SET_PIXEL_FORMAT, &mut format as *mut c_uint as *mut c_void) {
+        return false;
+    }
+
+    let options = get_options();
+    if rom.is_empty() || rom.len() > options.target.max_rom_size() {
+        return false;
+    }
+    let mut chip8 = Chip8::new(options.target.clone(), options.clock, rom);
+    chip8.set_timing(options.timing);
+    chip8.set_palette(options.palette.colors());
+    if let Some(font) = options.font {
+        chip8.set_font(&font.bytes());
+    }
+    chip8.set_num_output_channels(2);
+    chip8.set_seconds_per_output_sample((1.0 / SAMPLE_RATE) as f32);
+    *RETRO.lock().unwrap() = Some(Retro {
+        chip8,
+        target: options.target,
+        frame: Vec::new(),
+        samples: Vec::new()
+    });
+    true
+}
+
+#[no_mangle]
+pub extern "C" fn retro_load_game_special(_game_type: c_uint, _info: *const RetroGameInfo, _num_info: usize) -> bool {
+    false
+}
+
+#[no_mangle]
+pub extern "C" fn retro_unload_game() {
+    *RETRO.lock().unwrap() = None;
+}
+
+#[no_mangle]
+pub extern "C" fn retro_get_region() -> c_uint {
+    RETRO_REGION_NTSC
+}
+
+#[no_mangle]
+pub extern "C" fn retro_get_memory_data(_id: c_uint) -> *mut c_void {
+    std::ptr::null_mut()
+}
+
+#[no_mangle]
+pub extern "C" fn retro_get_memory_size(_id: c_uint) -> usize {
+    0
+}
@@ -0,0 +1,192 @@
+// A minimal libretro frontend, which drives the core through its C API the same way RetroArch would
+
+use chippy_libretro::*;
+use std::ffi::{c_char, c_uint, c_void, CStr};
+use std::sync::Mutex;
+
+const RETRO_ENVIRONMENT_SET_PIXEL_FORMAT: c_uint = 10;
+const RETRO_ENVIRONMENT_GET_VARIABLE: c_uint = 15;
+const RETRO_ENVIRONMENT_SET_VARIABLES: c_uint = 16;
+const RETRO_ENVIRONMENT_GET_VARIABLE_UPDATE: c_uint = 17;
+const RETRO_ENVIRONMENT_SET_SYSTEM_AV_INFO: c_uint = 32;
+
+#[repr(C)]
+struct GameInfo {
+    path: *const c_char,
+    data: *const c_void,
+    size: usize,
+    meta: *const c_char
+}
+
+#[repr(C)]
+struct Variable {
+    key: *const c_char,
+    value: *const c_char
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct AvInfo {
+    base_width: c_uint,
+    base_height: c_uint,
+    max_width: c_uint,
+    max_height: c_uint,
+    aspect_ratio: f32,
+    fps: f64,
+    sample_rate: f64
+}
+
+// What the core handed to the frontend each frame, and the display sizes and frame rates it switched to
+struct Output {
+    video: Vec<(c_uint, c_uint, usize)>,
+    audio: Vec<Vec<i16>>,
+    av_info: Vec<(c_uint, c_uint, f64)>
+}
+
+static OUTPUT: Mutex<Output> = Mutex::new(Output {
+    video: Vec::new(),
+    audio: Vec::new(),
+    av_info: Vec::new()
+});
+
+// Core options the user has set, and whether they changed since the core last checked.
+// Options that aren't set are left to the core's defaults.
+static OPTIONS: Mutex<(Vec<(&CStr, &CStr)>, bool)> = Mutex::new((Vec::new(), false));
+
+fn set_option(key: &'static CStr, value: &'static CStr) {
+    let mut options = OPTIONS.lock().unwrap();
+    options.0.retain(|option| option.0 != key);
+    options.0.push((key, value));
+    options.1 = true;
+}
+
+extern "C" fn environment(cmd: c_uint, data: *mut c_void) -> bool {
+    match cmd {
+        RETRO_ENVIRONMENT_GET_VARIABLE => {
+            let _variable = unsafe { &mut *(data as *mut Variable) };
+            let _key = unsafe { CStr::from_ptr(_variable.key) };
+            match OPTIONS.lock().unwrap().0.iter().find(|option| option.0 == _key) {
+                Some(option) => {
+                    _variable.value = option.1.as_ptr();
+                    true
+                }
+                None => false
+            }
+        }
+        RETRO_ENVIRONMENT_GET_VARIABLE_UPDATE => {
+            unsafe { *(data as *mut bool) = std::mem::take(&mut OPTIONS.lock().unwrap().1) };
+            true
+        }
+        RETRO_ENVIRONMENT_SET_SYSTEM_AV_INFO => {
+            let _info = unsafe { &*(data as *const AvInfo) };
+            OUTPUT.lock().unwrap().av_info.push((_info.base_width, _info.base_height, _info.fps));
+            true
+        }
+        _ => matches!(cmd, RETRO_ENVIRONMENT_SET_PIXEL_FORMAT | RETRO_ENVIRONMENT_SET_VARIABLES)
+    }
+}
+
+extern "C" fn video_refresh(_data: *const c_void, width: c_uint, height: c_uint, pitch: usize) {
+    OUTPUT.lock().unwrap().video.push((width, height, pitch));
+}
+
+extern "C" fn audio_sample_batch(data: *const i16, frames: usize) -> usize {
+    let _samples = unsafe { std::slice::from_raw_parts(data, frames * 2) };
+    OUTPUT.lock().unwrap().audio.push(_samples.to_vec());
+    frames
+}
+
+extern "C" fn input_poll() {}
+
+extern "C" fn input_state(_port: c_uint, _device: c_uint, _index: c_uint, _id: c_uint) -> i16 {
+    0
+}
+
+fn load_game(rom: &[u8]) -> bool {
+    let _game = GameInfo {
+        path: std::ptr::null(),
+        data: rom.as_ptr() as *const c_void,
+        size: rom.len(),
+        meta: std::ptr::null()
+    };
+    unsafe { retro_load_game(&_game as *const GameInfo as *const RetroGameInfo) }
+}
+
+// The core keeps its state in globals, so everything runs in one test
+#[test]
+fn test_libretro_host() {
+    retro_set_environment(environment);
+    retro_set_video_refresh(video_refresh);
+    retro_set_audio_sample_batch(audio_sample_batch);
+    retro_set_input_poll(input_poll);
+    retro_set_input_state(input_state);
+    retro_init();
+
+    // Too big for the default target (XO-CHIP) with its 64K of memory
+    assert!(!load_game(&[0; 0x10000]));
+    assert!(!load_game(&[]));
+
+    // Start the buzzer with a long sound timer and loop forever
+    assert!(load_game(&[0x60, 0xFF, 0xF0, 0x18, 0x12, 0x04]));
+    let mut av_info = AvInfo::default();
+    unsafe { retro_get_system_av_info(&mut av_info as *mut AvInfo as *mut RetroSystemAvInfo) };
+    assert_eq!((av_info.base_width, av_info.base_height), (128, 64));
+    assert_eq!((av_info.fps, av_info.sample_rate), (60.0, 44100.0));
+
+    for _i in 0 .. 120 {
+        retro_run();
+    }
+    let output = OUTPUT.lock().unwrap();
+    assert_eq!(output.video.len(), 120);
+    assert!(output.video.iter().all(|video| *video == (128, 64, 128 * 4)));
+
+    // Every frame is a whole frame of samples. The end of each frame is longer than half a period of the buzzer,
+    // so it can't be one sample held to fill the frame out.
+    assert_eq!(output.audio.len(), 120);
+    assert!(output.audio.iter().all(|samples| samples.len() == 735 * 2));
+    for samples in &output.audio[1 ..] {
+        let _tail = &samples[samples.len() - 800 ..];
+        assert!(_tail.iter().any(|sample| *sample != _tail[0]));
+    }
+    drop(output);
+
+    // The volume and low-pass options apply from the next frame. Muted frames are silent,
+    // and the low-pass filter slows down how fast the buzzer's edges change.
+    let max_step = |samples: &[i16]| samples.windows(2).map(|pair| (pair[1] as i32 - pair[0] as i32).abs()).max().unwrap();
+    let _unfiltered = max_step(&OUTPUT.lock().unwrap().audio[119]);
+    set_option(c"chippy_volume", c"0%");
+    retro_run();
+    assert!(OUTPUT.lock().unwrap().audio[120].iter().all(|sample| *sample == 0));
+    set_option(c"chippy_volume", c"100%");
+    set_option(c"chippy_low_pass", c"500");
+    retro_run();
+    retro_run();
+    assert!(max_step(&OUTPUT.lock().unwrap().audio[122]) * 2 < _unfiltered);
+    assert!(OUTPUT.lock().unwrap().av_info.is_empty());
+
+    // Changing the target restarts the game and tells the frontend the new display size and frame rate
+    set_option(c"chippy_target", c"mega-chip");
+    retro_run();
+    assert_eq!(OUTPUT.lock().unwrap().av_info, [(256, 192, 60.0)]);
+    assert_eq!(OUTPUT.lock().unwrap().video[123], (256, 192, 256 * 4));
+    set_option(c"chippy_target", c"dream-6800");
+    retro_run();
+    assert_eq!(OUTPUT.lock().unwrap().av_info[1 ..], [(128, 64, 50.0)]);
+    assert_eq!(OUTPUT.lock().unwrap().audio[124].len(), 882 * 2);
+    let mut av_info = AvInfo::default();
+    unsafe { retro_get_system_av_info(&mut av_info as *mut AvInfo as *mut RetroSystemAvInfo) };
+    assert_eq!((av_info.base_width, av_info.base_height, av_info.fps), (128, 64, 50.0));
+
+    // Other options don't
+    set_option(c"chippy_palette", c"green");
+    retro_run();
+    assert_eq!(OUTPUT.lock().unwrap().av_info.len(), 2);
+
+    // Save states round trip
+    let mut state = vec![0; retro_serialize_size()];
+    assert!(unsafe { retro_serialize(state.as_mut_ptr() as *mut c_void, state.len()) });
+    assert!(unsafe { retro_unserialize(state.as_ptr() as *const c_void, state.len()) });
+
+    retro_unload_game();
+    retro_deinit();
+}
@@ -0,0 +1,312 @@
+// The config file (config.toml), with defaults for every ROM and profiles for particular ROMs.
+// Top level settings are the defaults, with tables for quirks, keys and ROM profiles. ROM profiles are keyed by the ROM's
+// file name or SHA-1, e.g. [rom."pong.ch8"] or [rom."<sha1>".quirks]. Command line arguments override anything set here.
+// Problems are returned as ConfigErrors, which stop chippy with an exit code from sysexits.h like LoadErrors do.
+
+#[cfg(test)]
+mod test;
+
+use crate::core::{Palette, Persistence, Quirks, Target, Timing};
+use crate::gamepad::GamepadButton;
+use crate::keys::{HostKeymap, KeymapPreset};
+use crate::utils;
+use basic_emu_frontend::SyncModes;
+use clap::ValueEnum;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+const EXIT_NO_INPUT: i32 = 66;
+const EXIT_CONFIG: i32 = 78;
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Open(String, std::io::Error),
+    Parse(String, String)
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Open(path, error) => write!(f, "couldn't open {}: {}", path, error),
+            ConfigError::Parse(path, error) => write!(f, "couldn't read {}: {}", path, error)
+        }
+    }
+}
+
+impl ConfigError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ConfigError::Open(..) => EXIT_NO_INPUT,
+            ConfigError::Parse(..) => EXIT_CONFIG
+        }
+    }
+
+    // Show the error and stop
+    pub fn exit(&self) -> ! {
+        eprintln!("chippy: {}", self);
+        std::process::exit(self.exit_code())
+    }
+}
+
+// Settings from the config, anything that isn't set is left to the command line or the defaults
+#[derive(Default, Clone)]
+pub struct Profile {
+    pub target: Option<Target>,
+    pub clock: Option<u32>,
+    pub clock_hz: Option<f32>,
+    pub timing: Option<Timing>,
+    pub sync: Option<SyncModes>,
+    // Quirks to change from the target's own, by name
+    pub quirks: Vec<(String, bool)>,
+    pub palette: Option<[[u8; 4]; 16]>,
+    pub keymap: Option<KeymapPreset>,
+    pub keymap_file: Option<String>,
+    // Lines in the keymap file format, applied after the keymap file
+    pub keys: Vec<String>,
+    // Gamepad buttons to bind, without changing the keys' other host keys. These come from the ROM database.
+    pub buttons: Vec<(GamepadButton, usize)>,
+    pub persistence: Option<Persistence>,
+    pub blend_frames: Option<usize>,
+    pub volume: Option<f32>,
+    pub low_pass: Option<f32>
+}
+
+impl Profile {
+    // Apply the settings from another profile over these ones
+    pub fn merge(&mut self, other: &Profile) {
+        macro_rules! merge_options {
+            ($($field:ident),*) => {
+                $(
+                    if other.$field.is_some() {
+                        self.$field = other.$field.clone();
+                    }
+                )*
+            };
+        }
+        merge_options!(target, timing, sync, palette, keymap, keymap_file, persistence, blend_frames, volume, low_pass);
+        // The clock and clock-hz are two ways to set the same thing, so setting either replaces both
+        if other.clock.is_some() || other.clock_hz.is_some() {
+            self.clock = other.clock;
+            self.clock_hz = other.clock_hz;
+        }
+        self.quirks.extend(other.quirks.iter().cloned());
+        self.keys.extend(other.keys.iter().cloned());
+        self.buttons.extend(other.buttons.iter().copied());
+    }
+
+    // Change the quirks set in the profile
+    pub fn apply_quirks(&self, quirks: &mut Quirks) {
+        for (name, value) in &self.quirks {
+            set_quirk(quirks, name, *value);
+        }
+    }
+
+    // Bind the keys set in the profile
+    pub fn apply_keys(&self, keymap: &mut HostKeymap) {
+        for (button, index) in &self.buttons {
+            keymap.bind_button(*button, *index);
+        }
+        for line in &self.keys {
+            keymap.load_str(line).unwrap();
+        }
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct Config {
+    global: Profile,
+    roms: Vec<(String, Profile)>
+}
+
+impl Config {
+    // ~/.config/chippy/config.toml, or the same under $XDG_CONFIG_HOME or %APPDATA%
+    pub fn default_path() -> Option<PathBuf> {
+        let _config_dir = match std::env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) => PathBuf::from(dir),
+            None => match (std::env::var_os("HOME"), std::env::var_os("APPDATA")) {
+                (Some(home), _) => Path::new(&home).join(".config"),
+                (None, Some(app_data)) => PathBuf::from(app_data),
+                (None, None) => return None
+            }
+        };
+        Some(_config_dir.join("chippy").join("config.toml"))
+    }
+
+    // Load the config from a path, or from the default path if there's a config there
+    pub fn load(path: Option<&str>) -> Result<Config, ConfigError> {
+        let _path = match path {
+            Some(path) => PathBuf::from(path),
+            None => match Config::default_path() {
+                Some(path) if path.is_file() => path,
+                _ => return Ok(Config::default())
+            }
+        };
+        let _name = _path.display().to_string();
+        let _text = std::fs::read_to_string(&_path).map_err(|error| ConfigError::Open(_name.clone(), error))?;
+        Config::parse(&_text).map_err(|error| ConfigError::Parse(_name, error))
+    }
+
+    pub fn parse(text: &str) -> Result<Config, String> {
+        let _table: Table = text.parse().map_err(|error: toml::de::Error| {
+            let _line = error.span().map_or(1, |span| text[.. span.start].matches('\n').count() + 1);
+            format!("line {}: {}", _line, error.message().replace('\n', ", "))
+        })?;
+        let mut config = Config::default();
+        read_profile(&mut config.global, &_table, "", true)?;
+        if let Some(roms) = _table.get("rom") {
+            let Value::Table(roms) = roms else {
+                return Err(String::from("expected [rom.\"<file name or SHA-1>\"] tables"));
+            };
+            for (name, rom) in roms {
+                let _section = format!("rom.\"{}\"", name);
+                let Value::Table(rom) = rom else {
+                    return Err(format!("expected a table for [{}]", _section));
+                };
+                let mut profile = Profile::default();
+                read_profile(&mut profile, rom, &_section, false)?;
+                config.roms.push((name.clone(), profile));
+            }
+        }
+        Ok(config)
+    }
+
+    // The settings for a ROM: the defaults, then what's known about the ROM itself (the target for its extension and
+    // what the ROM database has for it), then the profile for its file name, then the profile for its SHA-1
+    pub fn profile(&self, rom_path: &str, rom: &[u8], known: &Profile) -> Profile {
+        let _file_name = Path::new(rom_path).file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        let _hash = utils::sha1_hex(rom);
+        let mut profile = self.global.clone();
+        profile.merge(known);
+        for key in [_file_name, _hash] {
+            for (name, rom_profile) in &self.roms {
+                if name.eq_ignore_ascii_case(&key) {
+                    profile.merge(rom_profile);
+                }
+            }
+        }
+        profile
+    }
+}
+
+// Read the settings and the quirks and keys tables of the top level or a ROM profile, section is the profile's table name
+fn read_profile(profile: &mut Profile, table: &Table, section: &str, top_level: bool) -> Result<(), String> {
+    let _in = |name: &str| match section {
+        "" => format!("[{}]", name),
+        _ => format!("[{}.{}]", section, name)
+    };
+    for (name, value) in table {
+        match (name.as_str(), value) {
+            ("rom", _) if top_level => (),
+            ("quirks", Value::Table(quirks)) => {
+                for (name, value) in quirks {
+                    let _error = |message: String| format!("{}: {}", _in("quirks"), message);
+                    let _value = value.as_bool().ok_or_else(|| _error(format!("expected true or false, found {}", value)))?;
+                    if !set_quirk(&mut Quirks::for_target(&Target::default()), name, _value) {
+                        return Err(_error(format!("unknown quirk {}", name)));
+                    }
+                    profile.quirks.push((name.clone(), _value));
+                }
+            }
+            ("keys", Value::Table(keys)) => {
+                for (name, value) in keys {
+                    // Checked now so that mistakes are reported with the config's table name
+                    let _line = format!("{} = {}", name, value);
+                    let _error_line = |message: String| format!("{}: {}", _in("keys"), message.split_once(": ").map_or(&message[..], |(_line, message)| message));
+                    HostKeymap::new(KeymapPreset::default()).load_str(&_line).map_err(_error_line)?;
+                    profile.keys.push(_line);
+                }
+            }
+            (_, Value::Table(_table)) => return Err(format!("unknown section {}", _in(name))),
+            _ => set_setting(profile, name, value).map_err(|message| match section {
+                "" => message,
+                _ => format!("[{}]: {}", section, message)
+            })?
+        }
+    }
+    Ok(())
+}
+
+fn set_setting(profile: &mut Profile, name: &str, value: &Value) -> Result<(), String> {
+    match name {
+        "target" => profile.target = Some(parse_enum(name, value)?),
+        "clock" => profile.clock = Some(parse_integer(value)?),
+        "clock-hz" => profile.clock_hz = Some(parse_float(value)?),
+        "timing" => profile.timing = Some(parse_enum(name, value)?),
+        "sync" => profile.sync = Some(parse_enum(name, value)?),
+        "palette" => profile.palette = Some(parse_palette(value)?),
+        "keymap" => profile.keymap = Some(parse_enum(name, value)?),
+        "keymap-file" => profile.keymap_file = Some(parse_string(value)?),
+        "persistence" => profile.persistence = Some(parse_enum(name, value)?),
+        "blend-frames" => profile.blend_frames = Some(parse_integer(value)?),
+        "volume" => profile.volume = Some(parse_float(value)?),
+        "low-pass" => profile.low_pass = Some(parse_float(value)?),
+        _ => return Err(format!("unknown setting {}", name))
+    }
+    Ok(())
+}
+
+// Set a quirk by its name in the config, returning false if there's no quirk with that name
+fn set_quirk(quirks: &mut Quirks, name: &str, value: bool) -> bool {
+    let _quirk = match name {
+        "vf-reset" => &mut quirks.vf_reset,
+        "memory-increment" => &mut quirks.memory_increment,
+        "memory-increment-by-x" => &mut quirks.memory_increment_by_x,
+        "shift" => &mut quirks.shift,
+        "jump" => &mut quirks.jump,
+        "display-wait" => &mut quirks.display_wait,
+        "wrap" => &mut quirks.wrap,
+        "collision-rows" => &mut quirks.collision_rows,
+        "four-planes" => &mut quirks.four_planes,
+        "pal-timing" => &mut quirks.pal_timing,
+        _ => return false
+    };
+    *_quirk = value;
+    true
+}
+
+fn parse_string(value: &Value) -> Result<String, String> {
+    value.as_str().map(str::to_string).ok_or_else(|| format!("expected a string, found {}", value))
+}
+
+fn parse_integer<T: TryFrom<i64>>(value: &Value) -> Result<T, String> {
+    value.as_integer().and_then(|value| T::try_from(value).ok()).ok_or_else(|| format!("expected a whole number, found {}", value))
+}
+
+// Floats can be written as whole numbers too
+fn parse_float(value: &Value) -> Result<f32, String> {
+    match value {
+        Value::Integer(value) => Ok(*value as f32),
+        Value::Float(value) => Ok(*value as f32),
+        _ => Err(format!("expected a number, found {}", value))
+    }
+}
+
+// Enums use the same names as on the command line
+fn parse_enum<T: ValueEnum>(name: &str, value: &Value) -> Result<T, String> {
+    let _value = parse_string(value)?;
+    T::from_str(&_value, true).map_err(|_error| format!("unknown {} {}", name, _value))
+}
+
+// One of the built in palettes by name, or a list of up to 16 "#rrggbb" colours replacing the start of the default palette
+fn parse_palette(value: &Value) -> Result<[[u8; 4]; 16], String> {
+    let Value::Array(_colors) = value else {
+        return Ok(parse_enum::<Palette>("palette", value)?.colors());
+    };
+    let mut palette = Palette::default().colors();
+    if _colors.len() > palette.len() {
+        return Err(format!("expected at most {} colours, found {}", palette.len(), _colors.len()));
+    }
+    for (i, color) in _colors.iter().enumerate() {
+        let _color = parse_string(color)?;
+        palette[i] = parse_color(&_color).ok_or_else(|| format!("expected a colour like \"#rrggbb\", found {}", _color))?;
+    }
+    Ok(palette)
+}
+
+// A colour written as #rrggbb
+pub fn parse_color(color: &str) -> Option<[u8; 4]> {
+    let _hex = color.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+    let _rgb = u32::from_str_radix(_hex, 16).ok()?;
+    Some([(_rgb >> 16) as u8, (_rgb >> 8) as u8, _rgb as u8, 0xff])
+}
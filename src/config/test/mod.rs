@@ -0,0 +1,138 @@
+use super::{Config, ConfigError, Profile};
+use crate::core::{Palette, Persistence, Quirks, Target};
+use crate::keys::{Binding, HostKeymap, KeymapPreset};
+use crate::utils;
+use basic_emu_frontend::VirtualKeyCode as K;
+
+const CONFIG: &str = r##"
+# Defaults for every ROM
+target = "xo"
+clock = 500
+persistence = "blend"
+blend-frames = 3
+palette = ["#000000", "#FFFFFF"]
+
+[quirks]
+wrap = true
+
+[rom."pong.ch8"]
+target = "chip"
+clock-hz = 700
+palette = "green"
+
+[rom."pong.ch8".quirks]
+vf-reset = false
+
+[rom."pong.ch8".keys]
+1 = ["W", "DPadUp"] # left paddle
+
+[rom.'a9993e364706816aba3e25717850c26c9cd0d89d']
+clock = 1_000
+"##;
+
+#[test]
+fn test_config_profiles() {
+    let config = Config::parse(CONFIG).unwrap();
+
+    // Another ROM just gets the defaults
+    let other = config.profile("roms/other.ch8", &[0x00, 0xE0], &Profile::default());
+    assert_eq!(other.target, Some(Target::XO));
+    assert_eq!(other.clock, Some(500));
+    assert_eq!(other.clock_hz, None);
+    assert_eq!(other.persistence, Some(Persistence::Blend));
+    assert_eq!(other.blend_frames, Some(3));
+    let mut palette = Palette::default().colors();
+    palette[0] = [0x00, 0x00, 0x00, 0xff];
+    palette[1] = [0xff, 0xff, 0xff, 0xff];
+    assert_eq!(other.palette, Some(palette));
+    assert!(other.keys.is_empty());
+
+    // Profiles by file name apply over the defaults
+    let pong = config.profile("/home/me/roms/Pong.ch8", &[0x00, 0xE0], &Profile::default());
+    assert_eq!(pong.target, Some(Target::Chip));
+    assert_eq!(pong.clock, None);
+    assert_eq!(pong.clock_hz, Some(700.0));
+    assert_eq!(pong.palette, Some(Palette::Green.colors()));
+    let mut quirks = Quirks::for_target(&Target::Chip);
+    pong.apply_quirks(&mut quirks);
+    assert!(quirks.wrap);
+    assert!(!quirks.vf_reset);
+    let mut keymap = HostKeymap::new(KeymapPreset::Qwerty);
+    pong.apply_keys(&mut keymap);
+    assert_eq!(keymap.host_keys().iter().position(|key| *key == K::W).map(|i| keymap.bindings()[i]), Some(Binding::Keypad(0x1)));
+
+    // And profiles by SHA-1, "abc" is the standard test vector
+    assert_eq!(utils::sha1_hex(b"abc"), "a9993e364706816aba3e25717850c26c9cd0d89d");
+    assert_eq!(utils::sha1_hex(b""), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+    assert_eq!(config.profile("abc.ch8", b"abc", &Profile::default()).clock, Some(1000));
+}
+
+#[test]
+fn test_profile_order() {
+    let config = Config::parse(CONFIG).unwrap();
+    // What's known about the ROM, from its extension and the ROM database
+    let mut known = Profile { target: Some(Target::SuperModern), clock: Some(30), ..Profile::default() };
+    known.quirks.push((String::from("wrap"), false));
+    known.palette = Some(Palette::Amber.colors());
+
+    // It overrides the defaults
+    let other = config.profile("other.ch8", &[0x00, 0xE0], &known);
+    assert_eq!(other.target, Some(Target::SuperModern));
+    assert_eq!(other.clock, Some(30));
+    assert_eq!(other.palette, Some(Palette::Amber.colors()));
+    assert_eq!(other.persistence, Some(Persistence::Blend));
+    let mut quirks = Quirks::for_target(&Target::SuperModern);
+    other.apply_quirks(&mut quirks);
+    assert!(!quirks.wrap);
+
+    // And the profiles for the ROM override it
+    let pong = config.profile("pong.ch8", &[0x00, 0xE0], &known);
+    assert_eq!(pong.target, Some(Target::Chip));
+    assert_eq!(pong.clock, None);
+    assert_eq!(pong.clock_hz, Some(700.0));
+    assert_eq!(pong.palette, Some(Palette::Green.colors()));
+}
+
+#[test]
+fn test_clock_order() {
+    // A clock from a more specific profile wins over a clock-hz from a less specific one, and the other way around
+    let config = Config::parse("clock-hz = 900\n\n[rom.\"pong.ch8\"]\nclock = 20\n\n[rom.\"tank.ch8\"]\nclock-hz = 600").unwrap();
+    let pong = config.profile("pong.ch8", &[0x00, 0xE0], &Profile::default());
+    assert_eq!((pong.clock, pong.clock_hz), (Some(20), None));
+    let other = config.profile("other.ch8", &[0x00, 0xE0], &Profile::default());
+    assert_eq!((other.clock, other.clock_hz), (None, Some(900.0)));
+
+    // Like the tickrate from the ROM database
+    let known = Profile { clock: Some(15), ..Profile::default() };
+    let other = config.profile("other.ch8", &[0x00, 0xE0], &known);
+    assert_eq!((other.clock, other.clock_hz), (Some(15), None));
+    let tank = config.profile("tank.ch8", &[0x00, 0xE0], &known);
+    assert_eq!((tank.clock, tank.clock_hz), (None, Some(600.0)));
+}
+
+#[test]
+fn test_config_errors() {
+    let _error = |text: &str| Config::parse(text).err();
+    assert_eq!(_error("target = \"chip\"\n\nspeed = 5"), Some(String::from("unknown setting speed")));
+    assert_eq!(_error("[rom.\"pong.ch8\"]\nspeed = 5"), Some(String::from("[rom.\"pong.ch8\"]: unknown setting speed")));
+    assert_eq!(_error("target = \"chip-9\""), Some(String::from("unknown target chip-9")));
+    assert_eq!(_error("clock = 1.5"), Some(String::from("expected a whole number, found 1.5")));
+    assert_eq!(_error("palette = [\"#12345\"]"), Some(String::from("expected a colour like \"#rrggbb\", found #12345")));
+    assert_eq!(_error("[quirks]\nwobble = true"), Some(String::from("[quirks]: unknown quirk wobble")));
+    assert_eq!(_error("[quirks]\nwrap = \"yes\""), Some(String::from("[quirks]: expected true or false, found \"yes\"")));
+    assert_eq!(_error("[keys]\n5 = \"Whoops\""), Some(String::from("[keys]: unknown host key Whoops")));
+    assert_eq!(_error("[rom.\"pong.ch8\".video]"), Some(String::from("unknown section [rom.\"pong.ch8\".video]")));
+    assert_eq!(_error("rom = 5"), Some(String::from("expected [rom.\"<file name or SHA-1>\"] tables")));
+
+    // Syntax errors come from the TOML parser, with the line they're on
+    assert_eq!(_error("target = \"chip\"\nclock = fast"), Some(String::from("line 2: invalid string, expected `\"`, `'`")));
+    assert!(_error("[rom.\"pong.ch8]").unwrap().starts_with("line 1: "));
+
+    // Loading reports the file, and the exit code says whether it couldn't be opened or was wrong
+    let error = Config::load(Some("missing/config.toml")).err().unwrap();
+    assert_eq!(error.exit_code(), 66);
+    assert!(error.to_string().starts_with("couldn't open missing/config.toml: "));
+    let error = ConfigError::Parse(String::from("config.toml"), String::from("unknown setting speed"));
+    assert_eq!(error.exit_code(), 78);
+    assert_eq!(error.to_string(), "couldn't read config.toml: unknown setting speed");
+}
@@ -0,0 +1,206 @@
+// Band-limited audio output. The 1-bit pattern is integrated exactly over each step at OVERSAMPLE times the output rate,
+// which is then low-pass filtered down to the output rate with a windowed sinc, so high pitches don't alias.
+// Like Octo, the pattern is played from the most significant bit of its first byte, and the oscillator runs freely,
+// keeping its phase while the buzzer is silent. The pattern, pitch and sound timer are integrated per instruction,
+// so changing them partway through a sample only affects the part of the sample after the change.
+
+use super::Chip8;
+use std::f32::consts::PI;
+
+const OVERSAMPLE: usize = 4;
+// Length of the decimation filter at the oversampled rate
+const FILTER_TAPS: usize = 96;
+// Cutoff of the decimation filter as a fraction of the output sample rate, a bit under Nyquist
+const FILTER_CUTOFF: f32 = 0.44;
+// Level of a full scale sample at volume 1
+const OUTPUT_LEVEL: f32 = 0.15;
+// The buzzer is a square wave between -BUZZER_LEVEL and BUZZER_LEVEL
+const BUZZER_LEVEL: f32 = 0.5;
+
+// Output settings and filter state, these are only used for presenting audio so they aren't part of save states
+pub struct AudioState {
+    pub volume: f32,
+    // Cutoff of the extra one-pole low-pass filter in Hz, 0 disables it
+    pub low_pass: f32,
+    taps: Vec<f32>,
+    history: Vec<f32>,
+    history_pos: usize,
+    low_pass_value: f32,
+    // The oversampled steps of the output sample in progress, and the integral of the step in progress
+    steps: [f32; OVERSAMPLE],
+    step_index: usize,
+    step_sum: f32,
+    // Interleaved output samples recorded since recording started or was last taken
+    pub(super) recording: Option<Vec<f32>>
+}
+
+impl AudioState {
+    pub fn new() -> AudioState {
+        // Blackman windowed sinc, normalized for unity gain
+        let _center = (FILTER_TAPS - 1) as f32 / 2.0;
+        let _cutoff = FILTER_CUTOFF / OVERSAMPLE as f32;
+        let mut taps: Vec<f32> = (0 .. FILTER_TAPS).map(|i| {
+            let _t = i as f32 - _center;
+            let _sinc = if _t == 0.0 { 2.0 * _cutoff } else { (2.0 * PI * _cutoff * _t).sin() / (PI * _t) };
+            let _phase = 2.0 * PI * i as f32 / (FILTER_TAPS - 1) as f32;
+            _sinc * (0.42 - 0.5 * _phase.cos() + 0.08 * (2.0 * _phase).cos())
+        }).collect();
+        let _sum: f32 = taps.iter().sum();
+        taps.iter_mut().for_each(|tap| *tap /= _sum);
+        AudioState {
+            volume: 1.0,
+            low_pass: 0.0,
+            taps,
+            history: vec![0.0; FILTER_TAPS],
+            history_pos: 0,
+            low_pass_value: 0.0,
+            steps: [0.0; OVERSAMPLE],
+            step_index: 0,
+            step_sum: 0.0,
+            recording: None
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.history.fill(0.0);
+        self.low_pass_value = 0.0;
+        self.step_index = 0;
+        self.step_sum = 0.0;
+    }
+
+    fn push(&mut self, value: f32) {
+        self.history[self.history_pos] = value;
+        self.history_pos = (self.history_pos + 1) % FILTER_TAPS;
+    }
+
+    // Filter the oversampled history down to one output sample
+    fn decimate(&self) -> f32 {
+        let (_newer, _older) = self.history.split_at(self.history_pos);
+        _older.iter().chain(_newer.iter()).zip(self.taps.iter()).map(|(value, tap)| value * tap).sum()
+    }
+}
+
+// The bit of pattern at position, counting from the most significant bit
+fn pattern_bit(pattern: u128, position: f32) -> f32 {
+    ((pattern >> (127 - position as u32 % 128)) & 1) as f32
+}
+
+// The average of the bits of pattern from position start (in bits) over the next width bits
+fn pattern_average(pattern: u128, start: f32, width: f32) -> f32 {
+    if width <= 0.0 {
+        return pattern_bit(pattern, start);
+    }
+    let _end = start + width;
+    let mut pos = start;
+    let mut sum = 0.0;
+    while pos < _end {
+        let _next = (pos.floor() + 1.0).min(_end);
+        sum += pattern_bit(pattern, pos) * (_next - pos);
+        pos = _next;
+    }
+    sum / width
+}
+
+impl Chip8 {
+    pub fn set_volume(&mut self, volume: f32) {
+        self.audio.volume = volume.max(0.0);
+    }
+
+    pub fn set_low_pass(&mut self, hz: f32) {
+        self.audio.low_pass = hz.max(0.0);
+    }
+
+    // Record every output sample from now on, across resets and state loads
+    pub fn start_audio_recording(&mut self) {
+        self.audio.recording = Some(Vec::new());
+    }
+
+    // Take the samples recorded so far, recording carries on
+    pub fn take_audio_recording(&mut self) -> Vec<f32> {
+        self.audio.recording.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    // Take the rest of the recorded samples and stop recording
+    pub fn stop_audio_recording(&mut self) -> Vec<f32> {
+        self.audio.recording.take().unwrap_or_default()
+    }
+
+    pub fn get_num_output_channels(&self) -> usize {
+        self.num_output_channels
+    }
+
+    // The output sample rate in Hz set by the frontend, or 0 if it hasn't been set
+    pub fn get_sample_rate(&self) -> u32 {
+        if self.seconds_per_output_sample > 0.0 {
+            (1.0 / self.seconds_per_output_sample).round() as u32
+        } else {
+            0
+        }
+    }
+
+    // Run the audio on past the instructions until at least frames samples per channel are queued,
+    // for frontends that need a fixed number of samples each frame
+    pub fn queue_samples(&mut self, frames: usize) {
+        if self.seconds_per_output_sample <= 0.0 {
+            return;
+        }
+        while self.sample_queue.len() < frames * self.num_output_channels {
+            self.run_audio(self.seconds_per_output_sample / OVERSAMPLE as f32);
+        }
+    }
+
+    // Output audio samples for the time spent running an instruction
+    pub(super) fn run_audio(&mut self, elapsed: f32) {
+        if self.seconds_per_output_sample <= 0.0 {
+            return;
+        }
+        // audio_time is the time spent in the oversampled step in progress
+        let _step_time = self.seconds_per_output_sample / OVERSAMPLE as f32;
+        let mut remaining = elapsed;
+        loop {
+            let _step_end = remaining >= _step_time - self.audio_time;
+            let _chunk = if _step_end { (_step_time - self.audio_time).max(0.0) } else { remaining };
+            let _width = _chunk * self.audio_frequency;
+            if self.r_audio > 0 {
+                self.audio.step_sum += (pattern_average(self.audio_buffer, self.audio_oscillator, _width) * 2.0 - 1.0) * BUZZER_LEVEL * _chunk;
+            }
+            self.audio_oscillator = (self.audio_oscillator + _width) % 128.0;
+            if !_step_end {
+                self.audio_time += _chunk;
+                break;
+            }
+            remaining -= _chunk;
+            self.audio_time = 0.0;
+            self.audio.steps[self.audio.step_index] = self.audio.step_sum / _step_time;
+            self.audio.step_sum = 0.0;
+            self.audio.step_index += 1;
+            if self.audio.step_index == OVERSAMPLE {
+                self.audio.step_index = 0;
+                self.output_sample();
+            }
+        }
+    }
+
+    // Filter the finished oversampled steps down to one output sample
+    fn output_sample(&mut self) {
+        let _mega_sample = self.next_mega_sample();
+        for _i in 0 .. OVERSAMPLE {
+            let _value = _mega_sample.unwrap_or(self.audio.steps[_i]);
+            self.audio.push(_value);
+        }
+
+        let mut sample = self.audio.decimate();
+        if self.audio.low_pass > 0.0 {
+            let _alpha = 1.0 - (-2.0 * PI * self.audio.low_pass * self.seconds_per_output_sample).exp();
+            self.audio.low_pass_value += _alpha * (sample - self.audio.low_pass_value);
+            sample = self.audio.low_pass_value;
+        }
+        sample *= OUTPUT_LEVEL * self.audio.volume;
+        for _i in 0 .. self.num_output_channels {
+            self.sample_queue.push_back(sample);
+        }
+        if let Some(recording) = &mut self.audio.recording {
+            recording.extend(std::iter::repeat_n(sample, self.num_output_channels));
+        }
+    }
+}
@@ -0,0 +1,326 @@
+// The RCA CDP1802 CPU used in the COSMAC VIP, based on the RCA CDP1802 user manual (MPM-201).
+// Every instruction takes 2 machine cycles (8 clocks each), except the long branches/skips which take 3.
+
+// Memory and I/O connected to the CPU
+pub trait Bus {
+    fn read(&mut self, addr: u16) -> u8;
+    fn write(&mut self, addr: u16, value: u8);
+    // INP n (n is 1-7)
+    fn input(&mut self, port: u8) -> u8;
+    // OUT n (n is 1-7)
+    fn output(&mut self, port: u8, value: u8);
+    // The state of the EF1-EF4 flag lines (n is 1-4)
+    fn flag(&self, n: u8) -> bool;
+}
+
+pub struct Cdp1802 {
+    pub r: [u16; 16], // 16 scratchpad registers, any of them can be the program counter or data pointer
+    pub p: usize, // Which register is the program counter
+    pub x: usize, // Which register is the data pointer
+    pub d: u8, // Accumulator
+    pub df: bool, // Carry/borrow flag
+    pub t: u8, // X and P saved by an interrupt or MARK
+    pub ie: bool, // Interrupt enable
+    pub q: bool, // Q output
+    pub idle: bool // Waiting for an interrupt or DMA after IDL
+}
+
+impl Cdp1802 {
+    // The state after a reset
+    pub fn new() -> Cdp1802 {
+        Cdp1802 {
+            r: [0; 16],
+            p: 0,
+            x: 0,
+            d: 0,
+            df: false,
+            t: 0,
+            ie: true,
+            q: false,
+            idle: false
+        }
+    }
+
+    // Respond to an interrupt request, this takes 1 machine cycle
+    pub fn interrupt(&mut self) {
+        self.t = ((self.x as u8) << 4) | self.p as u8;
+        self.p = 1;
+        self.x = 2;
+        self.ie = false;
+        self.idle = false;
+    }
+
+    // DMA out cycle, returns the byte at R0 for the peripheral and advances R0. This takes 1 machine cycle.
+    pub fn dma_out(&mut self, bus: &mut impl Bus) -> u8 {
+        let value = bus.read(self.r[0]);
+        self.r[0] = self.r[0].wrapping_add(1);
+        self.idle = false;
+        value
+    }
+
+    fn fetch(&mut self, bus: &mut impl Bus) -> u8 {
+        let value = bus.read(self.r[self.p]);
+        self.r[self.p] = self.r[self.p].wrapping_add(1);
+        value
+    }
+
+    // M(R(X))
+    fn read_x(&self, bus: &mut impl Bus) -> u8 {
+        bus.read(self.r[self.x])
+    }
+
+    fn add(&mut self, a: u8, b: u8, carry: bool) {
+        let sum = a as u16 + b as u16 + carry as u16;
+        self.d = sum as u8;
+        self.df = sum > 0xFF;
+    }
+
+    // a - b, DF is set if there was no borrow
+    fn sub(&mut self, a: u8, b: u8, borrow: bool) {
+        let diff = a as i16 - b as i16 - borrow as i16;
+        self.d = diff as u8;
+        self.df = diff >= 0;
+    }
+
+    // Execute one instruction, returns the number of machine cycles it took
+    pub fn step(&mut self, bus: &mut impl Bus) -> u32 {
+        if self.idle {
+            return 1;
+        }
+        let op = self.fetch(bus);
+        let _n = (op & 0xF) as usize;
+        match op >> 4 {
+            0x0 if _n == 0 => {
+                // 00 - IDL
+                self.idle = true;
+            }
+            0x0 => {
+                // 0N - LDN
+                self.d = bus.read(self.r[_n]);
+            }
+            0x1 => {
+                // 1N - INC
+                self.r[_n] = self.r[_n].wrapping_add(1);
+            }
+            0x2 => {
+                // 2N - DEC
+                self.r[_n] = self.r[_n].wrapping_sub(1);
+            }
+            0x3 => {
+                // 3N - Short branches, the target replaces the low byte of R(P)
+                let _condition = match _n & 0x7 {
+                    0 => true,
+                    1 => self.q,
+                    2 => self.d == 0,
+                    3 => self.df,
+                    _ => bus.flag((_n & 0x7) as u8 - 3)
+                };
+                // 38 (SKP) is the inverse of the unconditional branch, so it always skips the target byte
+                if _condition != (_n >= 8) {
+                    let _target = bus.read(self.r[self.p]);
+                    self.r[self.p] = (self.r[self.p] & 0xFF00) | _target as u16;
+                } else {
+                    self.r[self.p] = self.r[self.p].wrapping_add(1);
+                }
+            }
+            0x4 => {
+                // 4N - LDA
+                self.d = bus.read(self.r[_n]);
+                self.r[_n] = self.r[_n].wrapping_add(1);
+            }
+            0x5 => {
+                // 5N - STR
+                bus.write(self.r[_n], self.d);
+            }
+            0x6 if _n == 0 => {
+                // 60 - IRX
+                self.r[self.x] = self.r[self.x].wrapping_add(1);
+            }
+            0x6 if _n < 8 => {
+                // 61-67 - OUT
+                let _value = self.read_x(bus);
+                bus.output(_n as u8, _value);
+                self.r[self.x] = self.r[self.x].wrapping_add(1);
+            }
+            0x6 if _n == 8 => {
+                // 68 is only used by the CDP1804 and later, it does nothing on the CDP1802
+            }
+            0x6 => {
+                // 69-6F - INP
+                self.d = bus.input(_n as u8 - 8);
+                bus.write(self.r[self.x], self.d);
+            }
+            0x7 => match _n {
+                0x0 | 0x1 => {
+                    // 70 - RET / 71 - DIS
+                    let _value = self.read_x(bus);
+                    self.r[self.x] = self.r[self.x].wrapping_add(1);
+                    self.x = (_value >> 4) as usize;
+                    self.p = (_value & 0xF) as usize;
+                    self.ie = _n == 0;
+                }
+                0x2 => {
+                    // 72 - LDXA
+                    self.d = self.read_x(bus);
+                    self.r[self.x] = self.r[self.x].wrapping_add(1);
+                }
+                0x3 => {
+                    // 73 - STXD
+                    bus.write(self.r[self.x], self.d);
+                    self.r[self.x] = self.r[self.x].wrapping_sub(1);
+                }
+                0x4 => {
+                    // 74 - ADC
+                    let _value = self.read_x(bus);
+                    self.add(_value, self.d, self.df);
+                }
+                0x5 => {
+                    // 75 - SDB
+                    let _value = self.read_x(bus);
+                    self.sub(_value, self.d, !self.df);
+                }
+                0x6 => {
+                    // 76 - SHRC
+                    let _carry = self.df;
+                    self.df = self.d & 1 != 0;
+                    self.d = (self.d >> 1) | ((_carry as u8) << 7);
+                }
+                0x7 => {
+                    // 77 - SMB
+                    let _value = self.read_x(bus);
+                    self.sub(self.d, _value, !self.df);
+                }
+                0x8 => {
+                    // 78 - SAV
+                    bus.write(self.r[self.x], self.t);
+                }
+                0x9 => {
+                    // 79 - MARK
+                    self.t = ((self.x as u8) << 4) | self.p as u8;
+                    bus.write(self.r[2], self.t);
+                    self.x = self.p;
+                    self.r[2] = self.r[2].wrapping_sub(1);
+                }
+                0xA => {
+                    // 7A - REQ
+                    self.q = false;
+                }
+                0xB => {
+                    // 7B - SEQ
+                    self.q = true;
+                }
+                0xC => {
+                    // 7C - ADCI
+                    let _value = self.fetch(bus);
+                    self.add(_value, self.d, self.df);
+                }
+                0xD => {
+                    // 7D - SDBI
+                    let _value = self.fetch(bus);
+                    self.sub(_value, self.d, !self.df);
+                }
+                0xE => {
+                    // 7E - SHLC
+                    let _carry = self.df;
+                    self.df = self.d & 0x80 != 0;
+                    self.d = (self.d << 1) | _carry as u8;
+                }
+                _ => {
+                    // 7F - SMBI
+                    let _value = self.fetch(bus);
+                    self.sub(self.d, _value, !self.df);
+                }
+            },
+            0x8 => {
+                // 8N - GLO
+                self.d = self.r[_n] as u8;
+            }
+            0x9 => {
+                // 9N - GHI
+                self.d = (self.r[_n] >> 8) as u8;
+            }
+            0xA => {
+                // AN - PLO
+                self.r[_n] = (self.r[_n] & 0xFF00) | self.d as u16;
+            }
+            0xB => {
+                // BN - PHI
+                self.r[_n] = (self.r[_n] & 0x00FF) | (self.d as u16) << 8;
+            }
+            0xC => {
+                // CN - Long branches and skips
+                let _condition = match _n & 0x3 {
+                    0 => true,
+                    1 => self.q,
+                    2 => self.d == 0,
+                    _ => self.df
+                };
+                match _n {
+                    0x4 => {
+                        // C4 - NOP
+                    }
+                    0xC => {
+                        // CC - LSIE
+                        if self.ie {
+                            self.r[self.p] = self.r[self.p].wrapping_add(2);
+                        }
+                    }
+                    // C0-C3 and C9-CB are branches (the second half inverts the condition)
+                    0x0 ..= 0x3 | 0x9 ..= 0xB => {
+                        if _condition != (_n >= 8) {
+                            let _hi = bus.read(self.r[self.p]) as u16;
+                            let _lo = bus.read(self.r[self.p].wrapping_add(1)) as u16;
+                            self.r[self.p] = (_hi << 8) | _lo;
+                        } else {
+                            self.r[self.p] = self.r[self.p].wrapping_add(2);
+                        }
+                    }
+                    // C5-C8 and CD-CF are skips, C5-C7 skip if the condition is false and C8 always skips
+                    _ => {
+                        let _skip = if _n < 0xC { !_condition || _n == 0x8 } else { _condition };
+                        if _skip {
+                            self.r[self.p] = self.r[self.p].wrapping_add(2);
+                        }
+                    }
+                }
+                return 3;
+            }
+            0xD => {
+                // DN - SEP
+                self.p = _n;
+            }
+            0xE => {
+                // EN - SEX
+                self.x = _n;
+            }
+            _ => {
+                // F0-FF - Logic and arithmetic with M(R(X)), or with the immediate byte from F8 up
+                let _value = match _n {
+                    0x6 | 0xE => 0,
+                    0x0 ..= 0x7 => self.read_x(bus),
+                    _ => self.fetch(bus)
+                };
+                match _n & 0x7 {
+                    0x0 => self.d = _value, // LDX / LDI
+                    0x1 => self.d |= _value, // OR / ORI
+                    0x2 => self.d &= _value, // AND / ANI
+                    0x3 => self.d ^= _value, // XOR / XRI
+                    0x4 => self.add(_value, self.d, false), // ADD / ADI
+                    0x5 => self.sub(_value, self.d, false), // SD / SDI
+                    0x6 if _n == 0x6 => {
+                        // SHR
+                        self.df = self.d & 1 != 0;
+                        self.d >>= 1;
+                    }
+                    0x6 => {
+                        // SHL
+                        self.df = self.d & 0x80 != 0;
+                        self.d <<= 1;
+                    }
+                    _ => self.sub(self.d, _value, false) // SM / SMI
+                }
+            }
+        }
+        2
+    }
+}
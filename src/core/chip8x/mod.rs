@@ -0,0 +1,137 @@
+// CHIP-8X, the CHIP-8 interpreter for the COSMAC VIP with the RCA VP-590 colour board and a second keypad.
+// Based on the "chip-8x" column of https://chip8.gulrak.net/
+// Programs start at 0x300, otherwise it behaves like CHIP-8 apart from the opcodes below.
+
+use super::{Chip8, WIDTH};
+
+// The display is 64x32, colours are set for zones 8 pixels wide and either 1 (BxyN) or 4 (Bxy0) rows high
+const ZONE_COLUMNS: usize = 8;
+const ZONE_ROWS: usize = 32;
+const BIG_ZONE_HEIGHT: usize = 4;
+
+// Offset of the second keypad in the key states
+const KEYPAD_2: usize = 0x10;
+
+// The VP-595 sound board plays a tone of VP595_CLOCK / (n + 1) Hz for the value n sent with FxF8
+const VP595_CLOCK: f32 = 27535.0;
+const VP595_DEFAULT: u8 = 0x80;
+
+// Background colours in the order 02A0 cycles through them
+const BACKGROUND_COLORS: [[u8; 4]; 4] = [
+    [0x00, 0x00, 0x80, 0xff], // Blue
+    [0x00, 0x00, 0x00, 0xff], // Black
+    [0x00, 0x80, 0x00, 0xff], // Green
+    [0x80, 0x00, 0x00, 0xff] // Red
+];
+
+// Foreground colours, bit 0 is red, bit 1 is blue and bit 2 is green
+const FOREGROUND_COLORS: [[u8; 4]; 8] = [
+    [0x00, 0x00, 0x00, 0xff], // Black
+    [0xff, 0x00, 0x00, 0xff], // Red
+    [0x00, 0x00, 0xff, 0xff], // Blue
+    [0xff, 0x00, 0xff, 0xff], // Violet
+    [0x00, 0xff, 0x00, 0xff], // Green
+    [0xff, 0xff, 0x00, 0xff], // Yellow
+    [0x00, 0xff, 0xff, 0xff], // Aqua
+    [0xff, 0xff, 0xff, 0xff] // White
+];
+
+pub struct Chip8XState {
+    // Index into BACKGROUND_COLORS
+    pub background: u8,
+    // Foreground colour of each zone, indexed by pixel row and then by zone column
+    pub zones: [[u8; ZONE_COLUMNS]; ZONE_ROWS]
+}
+
+impl Chip8XState {
+    pub fn new() -> Chip8XState {
+        Chip8XState {
+            background: 0,
+            zones: [[1; ZONE_COLUMNS]; ZONE_ROWS] // The interpreter starts with everything red
+        }
+    }
+}
+
+impl Chip8 {
+    // Execute a CHIP-8X specific opcode, returns false if the opcode should be handled as CHIP-8 instead
+    pub(super) fn run_chip8x_opcode(&mut self, op: u16, skip_count: usize) -> bool {
+        let _n = (op & 0xF) as usize;
+        let _x = ((op & 0xF00) >> 8) as usize;
+        let _y = ((op & 0xF0) >> 4) as usize;
+        match op & 0xF000 {
+            0x0000 if op == 0x02A0 => {
+                // 02A0
+                // Cycle the background colour through blue, black, green and red.
+                self.chip8x.background = (self.chip8x.background + 1) % BACKGROUND_COLORS.len() as u8;
+            }
+            0x5000 if _n == 1 => {
+                // 5xy1
+                // Add Vy to Vx, with each of the two 3-bit fields added separately (without carrying into the other).
+                self.r_v[_x] = ((self.r_v[_x] & 0x77) + (self.r_v[_y] & 0x77)) & 0x77;
+            }
+            0xB000 => {
+                let _color = self.r_v[_y] & 0x7;
+                let _x_pos = self.r_v[_x] as usize;
+                let _y_pos = self.r_v[(_x + 1) & 0xF] as usize;
+                if _n == 0 {
+                    // Bxy0
+                    // Set the foreground colour of a block of 8x4 zones to Vy. The low nibbles of Vx and Vx+1 are the
+                    // column and row of the first zone, and the high nibbles are how many more zones to colour.
+                    for row in (_y_pos & 0xF) ..= (_y_pos & 0xF) + (_y_pos >> 4) {
+                        for column in (_x_pos & 0xF) ..= (_x_pos & 0xF) + (_x_pos >> 4) {
+                            for i in 0 .. BIG_ZONE_HEIGHT {
+                                self.chip8x.zones[(row * BIG_ZONE_HEIGHT + i) % ZONE_ROWS][column % ZONE_COLUMNS] = _color;
+                            }
+                        }
+                    }
+                } else {
+                    // Bxyn
+                    // Set the foreground colour of the 8 pixel wide zone containing pixel (Vx, Vx+1) to Vy for n rows.
+                    for i in 0 .. _n {
+                        self.chip8x.zones[(_y_pos + i) % ZONE_ROWS][(_x_pos >> 3) % ZONE_COLUMNS] = _color;
+                    }
+                }
+            }
+            0xE000 if op & 0xFF == 0xF2 => {
+                // ExF2
+                // Skip next instruction if key with the value of Vx is pressed on the second keypad.
+                if self.curr_keys[KEYPAD_2 + (self.r_v[_x] & 0xF) as usize] {
+                    self.r_pc += skip_count;
+                }
+            }
+            0xE000 if op & 0xFF == 0xF5 => {
+                // ExF5
+                // Skip next instruction if key with the value of Vx is not pressed on the second keypad.
+                if !self.curr_keys[KEYPAD_2 + (self.r_v[_x] & 0xF) as usize] {
+                    self.r_pc += skip_count;
+                }
+            }
+            0xF000 if op & 0xFF == 0xF8 => {
+                // FxF8
+                // Output Vx to the I/O port, which sets the pitch of the VP-595 sound board (0 is the default pitch).
+                let _value = if self.r_v[_x] == 0 { VP595_DEFAULT } else { self.r_v[_x] };
+                // The buzzer pattern repeats every 32 bits
+                self.audio_frequency = VP595_CLOCK / (_value as f32 + 1.0) * 32.0;
+            }
+            0xF000 if op & 0xFF == 0xFB => {
+                // FxFB
+                // Wait for input from the I/O port and store it in Vx. Nothing is ever connected, so it reads 0.
+                self.r_v[_x] = 0;
+            }
+            _ => return false
+        }
+        true
+    }
+
+    // Draw the frame for the CHIP-8X target, set pixels take the colour of their zone and the rest are the background
+    pub(super) fn draw_chip8x(&self, frame: &mut [u8]) {
+        for (i, pixel) in frame.chunks_exact_mut(4).enumerate() {
+            let (x, y) = ((i % WIDTH) >> 1, (i / WIDTH) >> 1);
+            if self.color_index(&self.buffer_planes, i) != 0 {
+                pixel.copy_from_slice(&FOREGROUND_COLORS[self.chip8x.zones[y][x >> 3] as usize]);
+            } else {
+                pixel.copy_from_slice(&BACKGROUND_COLORS[self.chip8x.background as usize]);
+            }
+        }
+    }
+}
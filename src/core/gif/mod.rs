@@ -0,0 +1,172 @@
+// A small animated GIF encoder. Each frame gets its own colour table, built from the colours it uses.
+
+use std::collections::HashMap;
+
+const MAX_CODES: u16 = 4096;
+const MAX_COLORS: usize = 256;
+
+pub struct GifEncoder {
+    output: Vec<u8>
+}
+
+impl GifEncoder {
+    // Start a GIF that loops forever
+    pub fn new(width: usize, height: usize) -> GifEncoder {
+        let mut output = Vec::new();
+        output.extend_from_slice(b"GIF89a");
+        output.extend_from_slice(&(width as u16).to_le_bytes());
+        output.extend_from_slice(&(height as u16).to_le_bytes());
+        // No global colour table, background colour 0, square pixels
+        output.extend_from_slice(&[0x00, 0x00, 0x00]);
+        output.extend_from_slice(&[0x21, 0xFF, 0x0B]);
+        output.extend_from_slice(b"NETSCAPE2.0");
+        output.extend_from_slice(&[0x03, 0x01, 0x00, 0x00, 0x00]);
+        GifEncoder {
+            output
+        }
+    }
+
+    // Add a frame of RGBA pixels covering the whole image, shown for delay hundredths of a second
+    pub fn write_frame(&mut self, width: usize, height: usize, pixels: &[[u8; 4]], delay: u16) {
+        let (colors, indices) = index_colors(pixels);
+        let mut table_bits = 1;
+        while (1 << table_bits) < colors.len() {
+            table_bits += 1;
+        }
+
+        // Graphic control extension, the frame replaces the last one
+        self.output.extend_from_slice(&[0x21, 0xF9, 0x04, 0x04]);
+        self.output.extend_from_slice(&delay.to_le_bytes());
+        self.output.extend_from_slice(&[0x00, 0x00]);
+
+        // Image descriptor with a local colour table
+        self.output.extend_from_slice(&[0x2C, 0x00, 0x00, 0x00, 0x00]);
+        self.output.extend_from_slice(&(width as u16).to_le_bytes());
+        self.output.extend_from_slice(&(height as u16).to_le_bytes());
+        self.output.push(0x80 | (table_bits - 1));
+        for i in 0 .. 1 << table_bits {
+            let color = colors.get(i).unwrap_or(&[0, 0, 0, 0]);
+            self.output.extend_from_slice(&color[0 .. 3]);
+        }
+
+        // Image data, in sub-blocks of up to 255 bytes
+        let _min_code_size = table_bits.max(2);
+        self.output.push(_min_code_size);
+        for block in lzw_encode(&indices, _min_code_size).chunks(255) {
+            self.output.push(block.len() as u8);
+            self.output.extend_from_slice(block);
+        }
+        self.output.push(0x00);
+    }
+
+    // Take the bytes encoded so far
+    pub fn take_output(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.output)
+    }
+
+    // Write the trailer and take the rest of the bytes
+    pub fn finish(mut self) -> Vec<u8> {
+        self.output.push(0x3B);
+        self.output
+    }
+}
+
+// Build a colour table for the pixels. Past 256 colours, pixels use the closest colour in the table.
+fn index_colors(pixels: &[[u8; 4]]) -> (Vec<[u8; 4]>, Vec<u8>) {
+    let mut colors: Vec<[u8; 4]> = Vec::new();
+    let mut lookup: HashMap<[u8; 4], u8> = HashMap::new();
+    let mut indices = Vec::with_capacity(pixels.len());
+    for pixel in pixels {
+        let _index = match lookup.get(pixel) {
+            Some(index) => *index,
+            None if colors.len() < MAX_COLORS => {
+                colors.push(*pixel);
+                lookup.insert(*pixel, (colors.len() - 1) as u8);
+                (colors.len() - 1) as u8
+            }
+            None => {
+                let _distance = |color: &[u8; 4]| (0 .. 3).map(|c| (color[c] as i32 - pixel[c] as i32).pow(2)).sum::<i32>();
+                (0 .. colors.len()).min_by_key(|i| _distance(&colors[*i])).unwrap() as u8
+            }
+        };
+        indices.push(_index);
+    }
+    (colors, indices)
+}
+
+// Packs variable length codes into bytes, least significant bit first
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u8
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u8) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += size;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+// GIF flavoured LZW, the code size grows as the table fills up and the table is cleared once it has 4096 codes
+fn lzw_encode(indices: &[u8], min_code_size: u8) -> Vec<u8> {
+    let _clear_code = 1_u16 << min_code_size;
+    let _end_code = _clear_code + 1;
+    let mut writer = BitWriter {
+        bytes: Vec::new(),
+        buffer: 0,
+        bits: 0
+    };
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut code_size = min_code_size + 1;
+    let mut next_code = _end_code + 1;
+    writer.write(_clear_code, code_size);
+
+    let mut prefix: Option<u16> = None;
+    for index in indices {
+        let Some(code) = prefix else {
+            prefix = Some(*index as u16);
+            continue;
+        };
+        if let Some(longer) = table.get(&(code, *index)) {
+            prefix = Some(*longer);
+            continue;
+        }
+        writer.write(code, code_size);
+        if next_code == MAX_CODES {
+            writer.write(_clear_code, code_size);
+            table.clear();
+            code_size = min_code_size + 1;
+            next_code = _end_code + 1;
+        } else {
+            table.insert((code, *index), next_code);
+            // The decoder adds codes one step behind, so it's ready for a bigger code after this one
+            if next_code == 1 << code_size {
+                code_size += 1;
+            }
+            next_code += 1;
+        }
+        prefix = Some(*index as u16);
+    }
+    if let Some(code) = prefix {
+        writer.write(code, code_size);
+        // The decoder adds a code for this one too before reading the end code
+        if next_code == 1 << code_size && next_code < MAX_CODES {
+            code_size += 1;
+        }
+    }
+    writer.write(_end_code, code_size);
+    writer.finish()
+}
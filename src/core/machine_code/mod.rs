@@ -0,0 +1,125 @@
+// Machine code subroutines (0nnn) for the CHIP-8 interpreters that ran on the COSMAC VIP.
+// The routine runs on a CDP1802 against the CHIP-8 memory, which is set up the way the VIP interpreter lays it out:
+// V0-VF at 0EF0, the display at 0F00, I in RA and the timers in R8. Routines return to the interpreter with D4 (SEP R4).
+// Like on the VIP, that means 0EF0-0FFF is overwritten with the registers and the display before each routine runs
+// (along with the stack below 0ECF), so CHIP-8 programs that call machine code can't keep their own data there.
+
+use super::{Chip8, HEIGHT};
+use super::cdp1802::{Bus, Cdp1802};
+use std::fmt;
+
+const V_ADDRESS: usize = 0xEF0;
+const DISPLAY_ADDRESS: usize = 0xF00;
+const STACK_ADDRESS: u16 = 0xECF;
+const RETURN_REGISTER: usize = 4;
+// Give up on routines that never return instead of hanging
+const MAX_INSTRUCTIONS: u32 = 100_000;
+
+// Why a machine code subroutine didn't run, or didn't finish
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MachineCodeError {
+    // A call into the interpreter's own routines below the start address, which aren't in memory
+    InterpreterCall(usize),
+    // The routine didn't return within MAX_INSTRUCTIONS instructions, so it was stopped where it was
+    NoReturn(usize)
+}
+
+impl fmt::Display for MachineCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MachineCodeError::InterpreterCall(addr) => write!(f, "skipped a call to the interpreter's machine code at {addr:03X}"),
+            MachineCodeError::NoReturn(addr) => write!(f, "stopped the machine code at {addr:03X} after {MAX_INSTRUCTIONS} instructions without a return")
+        }
+    }
+}
+
+struct MachineCodeBus<'a> {
+    mem: &'a mut [u8]
+}
+
+impl Bus for MachineCodeBus<'_> {
+    fn read(&mut self, addr: u16) -> u8 {
+        self.mem[addr as usize]
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        self.mem[addr as usize] = value;
+    }
+
+    // There's no hardware attached, the display and keypad are only available through memory
+    fn input(&mut self, _port: u8) -> u8 {
+        0
+    }
+
+    fn output(&mut self, _port: u8, _value: u8) {}
+
+    fn flag(&self, _n: u8) -> bool {
+        false
+    }
+}
+
+impl Chip8 {
+    // The first machine code problem since this was last called, the program carries on after them
+    pub fn take_machine_code_error(&mut self) -> Option<MachineCodeError> {
+        self.machine_code_error.take()
+    }
+
+    fn machine_code_failed(&mut self, error: MachineCodeError) {
+        self.machine_code_error.get_or_insert(error);
+    }
+
+    // Run the machine code subroutine at addr
+    pub(super) fn run_machine_code(&mut self, addr: usize) {
+        if addr < self.target.start_address() {
+            // The interpreter's own routines aren't in memory. 00E0 and 00EE are handled as opcodes,
+            // and the other calls into the interpreter are skipped.
+            self.machine_code_failed(MachineCodeError::InterpreterCall(addr));
+            return;
+        }
+
+        // Copy the registers and the low-res display into memory
+        self.mem[V_ADDRESS .. V_ADDRESS + 16].copy_from_slice(&self.r_v);
+        let _sync_display = !self.high_res && !self.two_page;
+        if _sync_display {
+            for (row, bytes) in self.mem[DISPLAY_ADDRESS ..].chunks_exact_mut(8).take(HEIGHT / 2).enumerate() {
+                bytes.copy_from_slice(&((self.active_planes[0][row] >> 64) as u64).to_be_bytes());
+            }
+        }
+        let mut cpu = Cdp1802::new();
+        cpu.p = 3;
+        cpu.x = 2;
+        cpu.ie = false;
+        cpu.r[2] = STACK_ADDRESS;
+        cpu.r[3] = addr as u16;
+        cpu.r[5] = self.r_pc as u16;
+        cpu.r[8] = ((self.r_delay as u16) << 8) | self.r_audio as u16;
+        cpu.r[0xA] = self.r_i as u16;
+        cpu.r[0xB] = DISPLAY_ADDRESS as u16;
+
+        let mut bus = MachineCodeBus { mem: &mut self.mem };
+        let mut returned = false;
+        for _i in 0 .. MAX_INSTRUCTIONS {
+            if cpu.p == RETURN_REGISTER || cpu.idle {
+                returned = true;
+                break;
+            }
+            cpu.step(&mut bus);
+        }
+        if !returned {
+            self.machine_code_failed(MachineCodeError::NoReturn(addr));
+        }
+
+        // Copy everything back, routines are allowed to change any of it
+        self.r_v.copy_from_slice(&self.mem[V_ADDRESS .. V_ADDRESS + 16]);
+        self.r_pc = cpu.r[5] as usize;
+        self.r_delay = (cpu.r[8] >> 8) as u8;
+        self.r_audio = cpu.r[8] as u8;
+        self.r_i = cpu.r[0xA] as usize;
+        if _sync_display {
+            for (row, bytes) in self.mem[DISPLAY_ADDRESS ..].chunks_exact(8).take(HEIGHT / 2).enumerate() {
+                let _pixels = u64::from_be_bytes(bytes.try_into().unwrap());
+                self.active_planes[0][row] = (_pixels as u128) << 64;
+            }
+        }
+    }
+}
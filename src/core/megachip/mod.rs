@@ -0,0 +1,353 @@
+// MEGA-CHIP 8, a SUPER-CHIP 1.1 extension by Revival Studios with a 256x192 display using 256 colours.
+// Based on the Mega8 documentation and https://chip8.gulrak.net/
+// Outside of MEGA-CHIP mode (0010), the target behaves like SUPER-CHIP 1.1 and the planes are used as normal.
+
+use super::{Chip8, HEIGHT, WIDTH};
+
+pub const MEGA_WIDTH: usize = 256;
+pub const MEGA_HEIGHT: usize = 192;
+
+// The sample data for 060n follows a header with the 16-bit sample rate, 24-bit length and 3 unused bytes
+const SOUND_HEADER_SIZE: usize = 8;
+
+// Blend modes for drawing sprites (080n)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlendMode {
+    Normal,
+    Alpha25,
+    Alpha50,
+    Alpha75,
+    Additive,
+    Multiply
+}
+
+impl BlendMode {
+    fn from_nibble(n: usize) -> BlendMode {
+        match n {
+            1 => BlendMode::Alpha25,
+            2 => BlendMode::Alpha50,
+            3 => BlendMode::Alpha75,
+            4 => BlendMode::Additive,
+            5 => BlendMode::Multiply,
+            _ => BlendMode::Normal
+        }
+    }
+
+    pub fn to_nibble(self) -> u8 {
+        match self {
+            BlendMode::Normal => 0,
+            BlendMode::Alpha25 => 1,
+            BlendMode::Alpha50 => 2,
+            BlendMode::Alpha75 => 3,
+            BlendMode::Additive => 4,
+            BlendMode::Multiply => 5
+        }
+    }
+
+    fn blend(self, src: [u8; 4], dest: [u8; 4]) -> [u8; 4] {
+        let mix = |alpha: u32| {
+            let mut out = [0xff; 4];
+            for c in 0 .. 3 {
+                out[c] = ((src[c] as u32 * alpha + dest[c] as u32 * (4 - alpha)) / 4) as u8;
+            }
+            out
+        };
+        match self {
+            BlendMode::Normal => src,
+            BlendMode::Alpha25 => mix(1),
+            BlendMode::Alpha50 => mix(2),
+            BlendMode::Alpha75 => mix(3),
+            BlendMode::Additive => [src[0].saturating_add(dest[0]), src[1].saturating_add(dest[1]), src[2].saturating_add(dest[2]), 0xff],
+            BlendMode::Multiply => [
+                (src[0] as u32 * dest[0] as u32 / 0xff) as u8,
+                (src[1] as u32 * dest[1] as u32 / 0xff) as u8,
+                (src[2] as u32 * dest[2] as u32 / 0xff) as u8,
+                0xff
+            ]
+        }
+    }
+}
+
+// A digitised sound started by 060n
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sound {
+    pub start: usize,
+    pub length: usize,
+    pub rate: f32,
+    pub position: f32,
+    pub looping: bool
+}
+
+pub struct MegaChipState {
+    // Whether MEGA-CHIP mode is enabled (0011), otherwise it behaves like SUPER-CHIP
+    pub enabled: bool,
+    // The palette, set with 02nn. Index 0 is always transparent when drawing sprites.
+    pub palette: [[u8; 4]; 256],
+    pub sprite_width: usize,
+    pub sprite_height: usize,
+    pub screen_alpha: u8,
+    pub collision_color: u8,
+    pub blend_mode: BlendMode,
+    // Palette indices and colours being drawn to, used for collisions and blending
+    pub active_indices: Vec<u8>,
+    pub active_colors: Vec<[u8; 4]>,
+    // Colours ready for rendering, these are updated by 00E0
+    pub buffer_colors: Vec<[u8; 4]>,
+    pub sound: Option<Sound>
+}
+
+impl MegaChipState {
+    pub fn new() -> MegaChipState {
+        let mut palette = [[0xff; 4]; 256];
+        palette[0] = [0x00, 0x00, 0x00, 0xff];
+        MegaChipState {
+            enabled: false,
+            palette,
+            sprite_width: 0,
+            sprite_height: 0,
+            screen_alpha: 0xff,
+            // Index 0 is the cleared background, so a default of 0 would set VF on any draw.
+            // Like Cadmium, 1 is used until the program picks one with 09nn.
+            collision_color: 1,
+            blend_mode: BlendMode::Normal,
+            active_indices: vec![0; MEGA_WIDTH * MEGA_HEIGHT],
+            active_colors: vec![[0x00, 0x00, 0x00, 0xff]; MEGA_WIDTH * MEGA_HEIGHT],
+            buffer_colors: vec![[0x00, 0x00, 0x00, 0xff]; MEGA_WIDTH * MEGA_HEIGHT],
+            sound: None
+        }
+    }
+
+    pub fn set_blend_mode(&mut self, n: u8) {
+        self.blend_mode = BlendMode::from_nibble(n as usize);
+    }
+
+    fn clear(&mut self) {
+        self.active_indices.fill(0);
+        self.active_colors.fill([0x00, 0x00, 0x00, 0xff]);
+    }
+
+    // Move the active screen by (dx, dy) pixels, filling the uncovered area with the background
+    fn scroll(&mut self, dx: isize, dy: isize) {
+        let mut indices = vec![0; MEGA_WIDTH * MEGA_HEIGHT];
+        let mut colors = vec![[0x00, 0x00, 0x00, 0xff]; MEGA_WIDTH * MEGA_HEIGHT];
+        for y in 0 .. MEGA_HEIGHT as isize {
+            for x in 0 .. MEGA_WIDTH as isize {
+                let (src_x, src_y) = (x - dx, y - dy);
+                if src_x < 0 || src_y < 0 || src_x >= MEGA_WIDTH as isize || src_y >= MEGA_HEIGHT as isize {
+                    continue;
+                }
+                let src = src_y as usize * MEGA_WIDTH + src_x as usize;
+                let dest = y as usize * MEGA_WIDTH + x as usize;
+                indices[dest] = self.active_indices[src];
+                colors[dest] = self.active_colors[src];
+            }
+        }
+        self.active_indices = indices;
+        self.active_colors = colors;
+    }
+}
+
+impl Chip8 {
+    // Execute a MEGA-CHIP specific opcode, returns false if the opcode should be handled as SUPER-CHIP instead
+    pub(super) fn run_mega_opcode(&mut self, op: u16) -> bool {
+        let _n = (op & 0xF) as usize;
+        let _x = ((op & 0xF00) >> 8) as usize;
+        let _y = ((op & 0xF0) >> 4) as usize;
+        let _nn = (op & 0xFF) as usize;
+        match op & 0xFF00 {
+            0x0000 => match op {
+                0x0010 => {
+                    // 0010
+                    // Disable MEGA-CHIP mode.
+                    self.mega.enabled = false;
+                }
+                0x0011 => {
+                    // 0011
+                    // Enable MEGA-CHIP mode.
+                    self.mega.enabled = true;
+                    self.mega.clear();
+                }
+                0x00E0 if self.mega.enabled => {
+                    // 00E0
+                    // Show the screen and clear it, in MEGA-CHIP mode this is when frames are presented.
+                    self.mega.buffer_colors.copy_from_slice(&self.mega.active_colors);
+                    self.mega.clear();
+                }
+                0x00FB if self.mega.enabled => {
+                    // 00FB
+                    // Scroll screen content right four pixels.
+                    self.mega.scroll(4, 0);
+                }
+                0x00FC if self.mega.enabled => {
+                    // 00FC
+                    // Scroll screen content left four pixels.
+                    self.mega.scroll(-4, 0);
+                }
+                _ if op & 0xFFF0 == 0x00B0 && self.mega.enabled => {
+                    // 00Bn
+                    // Scroll screen content up n pixels.
+                    self.mega.scroll(0, -(_n as isize));
+                }
+                _ if op & 0xFFF0 == 0x00C0 && self.mega.enabled => {
+                    // 00Cn
+                    // Scroll screen content down n pixels.
+                    self.mega.scroll(0, _n as isize);
+                }
+                _ => return false
+            },
+            // Some descriptions of MEGA-CHIP swap these two, with 01nn loading the palette and 02nn setting I.
+            // This follows the Mega8 documentation and the programs written for it, where 01nn is the four byte instruction.
+            0x0100 => {
+                // 01nn nnnn
+                // Set I to the 24-bit address nnnnnn. This is a four byte instruction.
+                self.r_i = (_nn << 16) | ((self.mega_byte(self.r_pc) as usize) << 8) | self.mega_byte(self.r_pc + 1) as usize;
+                self.r_pc += 2;
+            }
+            0x0200 => {
+                // 02nn
+                // Load nn colours into the palette starting at index 1, each colour is 4 bytes (ARGB) starting at I.
+                for _i in 0 .. _nn {
+                    let _addr = self.r_i + _i * 4;
+                    let _alpha = self.mega_byte(_addr);
+                    self.mega.palette[(_i + 1) & 0xFF] = [self.mega_byte(_addr + 1), self.mega_byte(_addr + 2), self.mega_byte(_addr + 3), _alpha];
+                }
+            }
+            0x0300 => {
+                // 03nn
+                // Set the sprite width to nn, 0 means 256.
+                self.mega.sprite_width = if _nn == 0 { 256 } else { _nn };
+            }
+            0x0400 => {
+                // 04nn
+                // Set the sprite height to nn, 0 means 256.
+                self.mega.sprite_height = if _nn == 0 { 256 } else { _nn };
+            }
+            0x0500 => {
+                // 05nn
+                // Set the screen alpha to nn.
+                self.mega.screen_alpha = _nn as u8;
+            }
+            0x0600 if op & 0xF0 == 0 => {
+                // 060n
+                // Play the digitised sound at I, looping if n is 0.
+                // The sample data is cut short at the end of memory, or left empty if the header is right at the end
+                let _rate = ((self.mega_byte(self.r_i) as usize) << 8) | self.mega_byte(self.r_i + 1) as usize;
+                let _length = ((self.mega_byte(self.r_i + 2) as usize) << 16) | ((self.mega_byte(self.r_i + 3) as usize) << 8) | self.mega_byte(self.r_i + 4) as usize;
+                let _start = self.r_i + SOUND_HEADER_SIZE;
+                self.mega.sound = Some(Sound {
+                    start: _start,
+                    length: _length.min(self.mem.len().saturating_sub(_start)),
+                    rate: _rate as f32,
+                    position: 0.0,
+                    looping: _n == 0
+                });
+            }
+            0x0700 if op == 0x0700 => {
+                // 0700
+                // Stop the digitised sound.
+                self.mega.sound = None;
+            }
+            0x0800 if op & 0xF0 == 0 => {
+                // 080n
+                // Set the sprite blend mode (0 normal, 1 25%, 2 50%, 3 75%, 4 additive, 5 multiply).
+                self.mega.set_blend_mode(_n as u8);
+            }
+            0x0900 => {
+                // 09nn
+                // Set the collision colour to palette index nn.
+                self.mega.collision_color = _nn as u8;
+            }
+            _ if op & 0xF000 == 0xD000 && self.mega.enabled => {
+                // Dxyn
+                // Draw a sprite using palette indices at (Vx, Vy), set VF if a pixel is drawn over the collision colour.
+                self.draw_mega_sprite(self.r_v[_x] as usize, self.r_v[_y] as usize, _n);
+            }
+            _ => return false
+        }
+        true
+    }
+
+    fn draw_mega_sprite(&mut self, x: usize, y: usize, n: usize) {
+        self.r_v[0xF] = 0;
+
+        // The built-in fonts are 1 bit per pixel, so draw them like normal sprites using the last palette colour
+        if self.r_i < 0x200 {
+            let _height = if n == 0 { 16 } else { n };
+            for _row in 0 .. _height {
+                let _byte = self.mega_byte(self.r_i + _row);
+                for _col in 0 .. 8 {
+                    if (_byte >> (7 - _col)) & 1 == 1 {
+                        self.put_mega_pixel(x + _col, y + _row, 0xFF);
+                    }
+                }
+            }
+            return;
+        }
+
+        for _row in 0 .. self.mega.sprite_height {
+            for _col in 0 .. self.mega.sprite_width {
+                let _index = self.mega_byte(self.r_i + _row * self.mega.sprite_width + _col);
+                if _index != 0 {
+                    self.put_mega_pixel(x + _col, y + _row, _index);
+                }
+            }
+        }
+    }
+
+    // Sprite and palette data running past the end of memory wraps around to the start, like the 24-bit I
+    fn mega_byte(&self, addr: usize) -> u8 {
+        self.mem[addr % self.mem.len()]
+    }
+
+    fn put_mega_pixel(&mut self, x: usize, y: usize, index: u8) {
+        if x >= MEGA_WIDTH || y >= MEGA_HEIGHT {
+            return;
+        }
+        let _i = y * MEGA_WIDTH + x;
+        if self.mega.active_indices[_i] == self.mega.collision_color {
+            self.r_v[0xF] = 1;
+        }
+        self.mega.active_indices[_i] = index;
+        self.mega.active_colors[_i] = self.mega.blend_mode.blend(self.mega.palette[index as usize], self.mega.active_colors[_i]);
+    }
+
+    // The next output sample of the digitised sound, if one is playing
+    pub(super) fn next_mega_sample(&mut self) -> Option<f32> {
+        let seconds_per_output_sample = self.seconds_per_output_sample;
+        let sound = self.mega.sound.as_mut()?;
+        if sound.length == 0 {
+            self.mega.sound = None;
+            return None;
+        }
+        let _sample = (self.mem[sound.start + sound.position as usize] as f32 - 128.0) / 128.0;
+        sound.position += sound.rate * seconds_per_output_sample;
+        if sound.position as usize >= sound.length {
+            if sound.looping {
+                sound.position %= sound.length as f32;
+            } else {
+                self.mega.sound = None;
+            }
+        }
+        Some(_sample)
+    }
+
+    // Draw the frame for the MEGA-CHIP target, which is always 256x192.
+    // Outside of MEGA-CHIP mode the 128x64 planes are doubled and centered vertically.
+    pub(super) fn draw_mega(&self, frame: &mut [u8]) {
+        let _offset = (MEGA_HEIGHT - HEIGHT * 2) / 2;
+        for (i, pixel) in frame.chunks_exact_mut(4).enumerate() {
+            let (x, y) = (i % MEGA_WIDTH, i / MEGA_WIDTH);
+            if self.mega.enabled {
+                let mut color = self.mega.buffer_colors[i];
+                for channel in color.iter_mut().take(3) {
+                    *channel = (*channel as u32 * self.mega.screen_alpha as u32 / 0xff) as u8;
+                }
+                pixel.copy_from_slice(&color);
+            } else if y < _offset || y >= _offset + HEIGHT * 2 {
+                pixel.copy_from_slice(&self.palette[0]);
+            } else {
+                pixel.copy_from_slice(&self.pixel_color((y - _offset) / 2 * WIDTH + x / 2));
+            }
+        }
+    }
+}
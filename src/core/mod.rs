@@ -6,8 +6,35 @@ use std::hash::{RandomState, BuildHasher, Hasher, DefaultHasher};
 use basic_emu_frontend::{Core, Frontend, keymap::Keymap, SyncModes};
 use std::collections::VecDeque;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use web_time::Instant;
 use gloo_utils::format::JsValueSerdeExt;
 
+mod audio;
+mod cdp1802;
+mod chip8x;
+mod font;
+mod gif;
+mod machine_code;
+mod megachip;
+mod persistence;
+mod state;
+mod video;
+mod vip;
+pub mod wav;
+
+use audio::AudioState;
+pub use font::FontSet;
+pub use machine_code::MachineCodeError;
+use font::{FONT_SIZE, SMALL_FONT_SIZE};
+pub use persistence::Persistence;
+pub use video::VideoFormat;
+use video::VideoRecording;
+use persistence::PersistenceState;
+use megachip::{MegaChipState, MEGA_HEIGHT, MEGA_WIDTH};
+use chip8x::Chip8XState;
+use vip::VipState;
+
 #[cfg(test)]
 mod test;
 
@@ -18,72 +45,339 @@ pub enum Target {
     SuperModern, // This is "schipc" in Gulrak's opcode table
     SuperLegacy, // This is "schip-1.1" in Gulrak's opcode table
     #[default]
-    XO // This is "xo-chip" in Gulrak's opcode table
+    XO, // This is "xo-chip" in Gulrak's opcode table
+    MegaChip, // This is "megachip8" in Gulrak's opcode table
+    #[value(name = "chip-8x")]
+    Chip8X, // This is "chip-8x" in Gulrak's opcode table
+    HiresChip, // The two-page "HIRES CHIP-8" interpreter for the VIP, with a 64x64 display
+    #[value(name = "chip-48")]
+    Chip48, // This is "chip-48" in Gulrak's opcode table
+    #[value(name = "super-1.0")]
+    Super10, // This is "schip-1.0" in Gulrak's opcode table
+    CosmacVip, // The original CHIP-8 interpreter running on an emulated COSMAC VIP, see vip/mod.rs
+    #[value(name = "eti-660")]
+    Eti660, // The ETI-660 learning computer, with a 64x48 display
+    #[value(name = "dream-6800")]
+    Dream6800 // CHIPOS on the DREAM 6800
+}
+
+impl Target {
+    // The recommended number of instructions to run per frame for each target
+    pub fn default_clock(&self) -> u32 {
+        match self {
+            Target::Chip => 11,
+            Target::SuperModern => 30,
+            Target::SuperLegacy => 30,
+            Target::XO => 30000,
+            Target::MegaChip => 3000,
+            Target::Chip8X => 11,
+            Target::HiresChip => 11,
+            Target::Chip48 => 30,
+            Target::Super10 => 30,
+            Target::CosmacVip => vip::CYCLES_PER_FRAME, // The VIP always runs at its real speed, this is just for reference
+            Target::Eti660 => 11,
+            Target::Dream6800 => 11
+        }
+    }
+
+    // The amount of memory available, MEGA-CHIP needs more for its 24-bit addresses
+    pub fn mem_size(&self) -> usize {
+        match self {
+            Target::MegaChip => 0x1000000,
+            _ => 0x10000
+        }
+    }
+
+    // Where programs are loaded and start running, the CHIP-8X interpreter is bigger so its programs start later
+    pub fn start_address(&self) -> usize {
+        match self {
+            Target::Chip8X => 0x300,
+            Target::Eti660 => 0x600,
+            _ => 0x200
+        }
+    }
+
+    // The largest ROM that fits between the start address and the end of the memory programs can address
+    pub fn max_rom_size(&self) -> usize {
+        let _end = match self {
+            Target::XO => 0x10000,
+            Target::MegaChip => self.mem_size(),
+            _ => 0x1000
+        };
+        _end - self.start_address()
+    }
+
+    // Where the 5-byte font is stored. On the DREAM 6800 low memory is used by CHIPOS, and the font is in its ROM at C000.
+    pub fn font_address(&self) -> usize {
+        match self {
+            Target::Dream6800 => 0xC000,
+            _ => 0
+        }
+    }
+
+    // The font the target's interpreter came with
+    pub fn default_font(&self) -> FontSet {
+        match self {
+            Target::Chip | Target::Chip8X | Target::HiresChip | Target::CosmacVip => FontSet::Vip,
+            Target::Chip48 | Target::Super10 | Target::SuperLegacy => FontSet::Schip11,
+            Target::Eti660 => FontSet::Eti660,
+            Target::Dream6800 => FontSet::Dream6800,
+            _ => FontSet::Octo
+        }
+    }
+
+    // The number of rows of the frame the planes are drawn to (MEGA-CHIP scales that frame up again).
+    // HIRES CHIP-8 has twice as many, so both axes of its 64x64 display are doubled like the low-res display's are,
+    // and the COSMAC VIP shows each of the CDP1861's 128 lines.
+    fn frame_height(&self) -> usize {
+        match self {
+            Target::HiresChip => HEIGHT * 2,
+            Target::CosmacVip => vip::DISPLAY_LINES,
+            _ => self.low_res_height() * 2
+        }
+    }
+
+    // The number of rows in low-res mode
+    fn low_res_height(&self) -> usize {
+        match self {
+            Target::Eti660 => 48,
+            _ => HEIGHT / 2
+        }
+    }
+
+    // Whether the SUPER-CHIP opcodes (scrolling, high-res mode, big font, etc.) are available
+    fn has_super_opcodes(&self) -> bool {
+        !matches!(self, Target::Chip | Target::Chip8X | Target::HiresChip | Target::Chip48 | Target::CosmacVip | Target::Eti660 | Target::Dream6800)
+    }
+
+    // Whether the scrolling opcodes 00Cn, 00FB and 00FC are available, these were added in SUPER-CHIP 1.1
+    fn has_scroll_opcodes(&self) -> bool {
+        self.has_super_opcodes() && *self != Target::Super10
+    }
+
+    // Whether 0nnn runs CDP1802 machine code, for the interpreters that ran on the COSMAC VIP
+    fn has_machine_code(&self) -> bool {
+        matches!(self, Target::Chip | Target::Chip8X | Target::HiresChip)
+    }
+
+    // SUPER-CHIP 1.1 display behaviour: switching resolution doesn't clear the screen, scrolling moves
+    // half as far in low-res mode, and Dxy0 draws 8x16 sprites in low-res mode
+    fn has_legacy_display(&self) -> bool {
+        matches!(self, Target::SuperLegacy | Target::MegaChip | Target::Super10)
+    }
+}
+
+// Behaviours that differ between interpreters and can be toggled separately from the target.
+// Each target has its own defaults, see Quirks::for_target.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quirks {
+    // 8xy1/8xy2/8xy3 reset VF to 0
+    pub vf_reset: bool,
+    // Fx55/Fx65 increment I by x + 1, otherwise I is left unchanged
+    pub memory_increment: bool,
+    // With memory_increment, I is incremented by x instead (CHIP-48 and SUPER-CHIP 1.0)
+    pub memory_increment_by_x: bool,
+    // 8xy6/8xyE shift Vx in place instead of shifting Vy into Vx
+    pub shift: bool,
+    // Bnnn jumps to xnn + Vx instead of nnn + V0
+    pub jump: bool,
+    // Dxyn waits for the start of the next frame in low-res mode
+    pub display_wait: bool,
+    // Sprites wrap around the edges of the screen instead of being clipped
+    pub wrap: bool,
+    // In high-res mode, Dxyn sets VF to the number of sprite rows that collided or were clipped off the bottom (SCHIP 1.1)
+    pub collision_rows: bool,
+    // XO-CHIP extension with four bit planes (16 colours), Fx01 takes a 4-bit plane mask instead of a 2-bit one
+    pub four_planes: bool,
+    // Frames and timers run at 50 Hz instead of 60 Hz, the Australian machines were built for PAL TVs
+    pub pal_timing: bool
+}
+
+#[wasm_bindgen]
+impl Quirks {
+    #[wasm_bindgen(constructor)]
+    pub fn new(target: Target) -> Quirks {
+        Quirks::for_target(&target)
+    }
+}
+
+impl Quirks {
+    pub fn for_target(target: &Target) -> Quirks {
+        let _super = matches!(target, Target::SuperModern | Target::SuperLegacy | Target::MegaChip | Target::Chip48 | Target::Super10);
+        // The other interpreters for 8-bit machines behave like the original CHIP-8
+        let _chip = matches!(target, Target::Chip | Target::Chip8X | Target::HiresChip | Target::Eti660 | Target::Dream6800);
+        Quirks {
+            vf_reset: _chip,
+            memory_increment: _chip || matches!(target, Target::XO | Target::Chip48 | Target::Super10),
+            memory_increment_by_x: matches!(target, Target::Chip48 | Target::Super10),
+            shift: _super,
+            jump: _super,
+            display_wait: _chip || matches!(target, Target::SuperLegacy | Target::MegaChip | Target::Super10),
+            wrap: *target == Target::XO,
+            collision_rows: *target == Target::SuperLegacy || *target == Target::MegaChip,
+            four_planes: false,
+            pal_timing: matches!(target, Target::Eti660 | Target::Dream6800)
+        }
+    }
+
+    // Frames (and timer ticks) per second
+    pub fn frame_rate(&self) -> f32 {
+        if self.pal_timing { 50.0 } else { 60.0 }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq)]
+pub enum Timing {
+    #[default]
+    Instructions, // Run the clock's number of instructions every frame
+    Vip // Charge each instruction what it cost on the COSMAC VIP interpreter against a per-frame cycle budget, see vip/mod.rs
+}
+
+#[wasm_bindgen]
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq)]
+pub enum Palette {
+    #[default]
+    Octo, // The default XO-CHIP colours from Octo
+    Grayscale,
+    Green, // Green phosphor monochrome
+    Amber // Amber phosphor monochrome
 }
 
-// The 5-byte hexadecimal font
-const SMALL_FONT_SET: [u8; 80] = [
-    0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
-    0x20, 0x60, 0x20, 0x20, 0x70, // 1
-    0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
-    0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
-    0x90, 0x90, 0xF0, 0x10, 0x10, // 4
-    0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
-    0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
-    0xF0, 0x10, 0x20, 0x40, 0x40, // 7
-    0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
-    0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
-    0xF0, 0x90, 0xF0, 0x90, 0x90, // A
-    0xE0, 0x90, 0xE0, 0x90, 0xE0, // B
-    0xF0, 0x80, 0x80, 0x80, 0xF0, // C
-    0xE0, 0x90, 0x90, 0x90, 0xE0, // D
-    0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
-    0xF0, 0x80, 0xF0, 0x80, 0x80, // F
-];
-
-// The 10-byte hexadecimal font from Octo
-const BIG_FONT_SET: [u8; 160] = [
-    0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, // 0
-    0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF, // 1
-    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // 2
-    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 3
-    0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03, // 4
-    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 5
-    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 6
-    0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18, // 7
-    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 8
-    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 9
-    0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
-    0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
-    0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
-    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
-    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
-    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // F
-];
+impl Palette {
+    // Colours indexed by which planes a pixel is set on (bit 0 for plane 0, bit 1 for plane 1, etc.)
+    // Only the first 4 are used unless the four plane XO-CHIP extension is enabled.
+    pub fn colors(&self) -> [[u8; 4]; 16] {
+        match self {
+            Palette::Octo => [
+                [0x66, 0x22, 0x00, 0xff],
+                [0xff, 0xcc, 0x00, 0xff],
+                [0xff, 0x66, 0x00, 0xff],
+                [0x99, 0x66, 0x00, 0xff],
+                [0x00, 0x99, 0xcc, 0xff],
+                [0x66, 0xcc, 0xff, 0xff],
+                [0x33, 0x66, 0xcc, 0xff],
+                [0x00, 0x33, 0x66, 0xff],
+                [0x99, 0x00, 0x66, 0xff],
+                [0xff, 0x66, 0xcc, 0xff],
+                [0xcc, 0x33, 0x99, 0xff],
+                [0x66, 0x00, 0x33, 0xff],
+                [0x33, 0x99, 0x33, 0xff],
+                [0x99, 0xff, 0x66, 0xff],
+                [0x66, 0xcc, 0x33, 0xff],
+                [0xff, 0xff, 0xff, 0xff]
+            ],
+            Palette::Grayscale => [
+                [0x00, 0x00, 0x00, 0xff],
+                [0xff, 0xff, 0xff, 0xff],
+                [0xaa, 0xaa, 0xaa, 0xff],
+                [0x55, 0x55, 0x55, 0xff],
+                [0x11, 0x11, 0x11, 0xff],
+                [0xee, 0xee, 0xee, 0xff],
+                [0x99, 0x99, 0x99, 0xff],
+                [0x44, 0x44, 0x44, 0xff],
+                [0x22, 0x22, 0x22, 0xff],
+                [0xdd, 0xdd, 0xdd, 0xff],
+                [0x88, 0x88, 0x88, 0xff],
+                [0x33, 0x33, 0x33, 0xff],
+                [0x66, 0x66, 0x66, 0xff],
+                [0xcc, 0xcc, 0xcc, 0xff],
+                [0xbb, 0xbb, 0xbb, 0xff],
+                [0x77, 0x77, 0x77, 0xff]
+            ],
+            Palette::Green => [
+                [0x0f, 0x38, 0x0f, 0xff],
+                [0x9b, 0xbc, 0x0f, 0xff],
+                [0x8b, 0xac, 0x0f, 0xff],
+                [0x30, 0x62, 0x30, 0xff],
+                [0x14, 0x44, 0x14, 0xff],
+                [0xc4, 0xf0, 0x2a, 0xff],
+                [0x7a, 0x9c, 0x0e, 0xff],
+                [0x26, 0x52, 0x26, 0xff],
+                [0x1c, 0x4e, 0x1c, 0xff],
+                [0xb0, 0xd8, 0x1e, 0xff],
+                [0x6a, 0x8c, 0x0d, 0xff],
+                [0x3a, 0x70, 0x3a, 0xff],
+                [0x48, 0x7e, 0x24, 0xff],
+                [0xde, 0xff, 0x5a, 0xff],
+                [0x5a, 0x7c, 0x0c, 0xff],
+                [0x44, 0x78, 0x44, 0xff]
+            ],
+            Palette::Amber => [
+                [0x1a, 0x0f, 0x00, 0xff],
+                [0xff, 0xb0, 0x00, 0xff],
+                [0xcc, 0x7a, 0x00, 0xff],
+                [0x80, 0x4c, 0x00, 0xff],
+                [0x2a, 0x19, 0x00, 0xff],
+                [0xff, 0xc8, 0x40, 0xff],
+                [0xb3, 0x6b, 0x00, 0xff],
+                [0x66, 0x3d, 0x00, 0xff],
+                [0x3b, 0x23, 0x00, 0xff],
+                [0xff, 0xd9, 0x80, 0xff],
+                [0x99, 0x5c, 0x00, 0xff],
+                [0x4d, 0x2e, 0x00, 0xff],
+                [0x59, 0x35, 0x00, 0xff],
+                [0xff, 0xe8, 0xb0, 0xff],
+                [0xe6, 0x94, 0x00, 0xff],
+                [0x73, 0x45, 0x00, 0xff]
+            ]
+        }
+    }
+}
 
 // Constants
-const FRAME_RATE: f32 = 60.0;
 const WIDTH: usize = 128;
 const HEIGHT: usize = 64;
-const PLANE_COUNT: usize = 2;
+const PLANE_COUNT: usize = 4; // Only XO-CHIP with the four plane extension uses all of them
+// HIRES CHIP-8 programs start with a jump into the interpreter patch, either to its entry at 0x244 or to 0x260 past the
+// patch's own setup. The patch sets up the 64x64 display and then runs the actual program from 0x2C0.
+const HIRES_ENTRIES: [usize; 2] = [0x244, 0x260];
+const HIRES_START: usize = 0x2C0;
+// The most frames run_frame will catch up on with host pacing, so the game doesn't race ahead after a stall
+const MAX_HOST_FRAMES: u32 = 4;
 
 pub struct Chip8 {
     // Public members
     // For high-res resolution mode
     high_res: bool,
+    // For the 64x64 HIRES CHIP-8 mode, this uses the low-res width with all of the rows
+    two_page: bool,
     // Planes ready for rendering
     buffer_planes: [[u128; HEIGHT]; PLANE_COUNT],
-    // Key press states
-    prev_keys: [bool; 16],
-    curr_keys: [bool; 16],
+    // Key press states, keys 0x10-0x1F are the second CHIP-8X keypad
+    prev_keys: [bool; 32],
+    curr_keys: [bool; 32],
 
     // Private members
     // The target platform
     target: Target,
-    // Instructions per second
-    clock: u32,
+    // Quirks, these default to the ones for the target
+    quirks: Quirks,
+    // Instructions per frame, this can be fractional when the clock is set in Hz
+    clock: f32,
+    // The clock in instructions per second if it was set that way, so it can follow frame rate changes (0 otherwise)
+    clock_hz: f32,
+    // The fraction of an instruction left over from previous frames
+    clock_carry: f32,
+    // Instructions (or VIP machine cycles) in the current frame
+    frame_budget: u32,
+    // How instructions are counted against the frame
+    timing: Timing,
+    // Frames per second, the timers are decremented once per frame
+    frame_rate: f32,
     // Remaining cycles for a frame
     remaining: u32,
+    // Cycles run since the timers were last decremented, they count down once per frame's worth of emulated time
+    timer_cycles: f32,
+    // Whether run_frame runs as many frames as fit in the real time since it was last called, instead of exactly one
+    host_pacing: bool,
+    // The time since some fixed point, real time unless set_host_clock replaced it
+    host_clock: Box<dyn Fn() -> Duration + Send>,
+    // When run_frame was last called, and the real time that hasn't been used for a frame yet.
+    // This goes negative when a frame was run slightly early.
+    last_host_frame: Option<Duration>,
+    host_time: f32,
     // General purpose registers
     r_v: [u8; 16], // 16 general purpose "Vx" registers (x is 0-F)
     // Indexing registers
@@ -98,141 +392,338 @@ pub struct Chip8 {
     // The ROM, this gets copied into memory on init/reset
     rom: Vec<u8>,
     // Memory
-    mem: [u8; 0x10000], // only XO-CHIP officially supports 0x10000, the rest have 0x1000 but just use the full range for simplicity
+    mem: Vec<u8>, // only XO-CHIP officially supports 0x10000, the rest have 0x1000 but just use the full range for simplicity. MEGA-CHIP has 0x1000000.
     // Halting flag (waiting for input/drawing)
     halting: bool,
     // Previous opcode, for halting purposes
     prev_op: u16,
-    // Display (128x64, 2 planes)
-    enabled_planes: u8, // Flags for which of the 2 planes to draw on. If the bit is set, draw on the plane.
+    // Display (128x64, 2 planes or 4 with the XO-CHIP four plane extension)
+    enabled_planes: u8, // Flags for which of the planes to draw on. If the bit is set, draw on the plane.
     active_planes: [[u128; HEIGHT]; PLANE_COUNT],
     // Audio
     num_output_channels: usize,
     seconds_per_output_sample: f32,
-    seconds_per_instruction: f32,
+    seconds_per_cycle: f32,
     audio_time: f32,
     audio_buffer: u128,
     audio_frequency: f32,
     audio_oscillator: f32,
     sample_queue: VecDeque<f32>,
+    audio: AudioState,
+    // Colours used when drawing, indexed by the plane bits of a pixel
+    palette: [[u8; 4]; 16],
+    // The small font followed by the big font, this is copied into memory on init/reset
+    font: [u8; FONT_SIZE],
+    // Whether set_font replaced the target's font, so changing the target keeps it
+    custom_font: bool,
+    // Display persistence mode and history
+    persistence: PersistenceState,
+    // MEGA-CHIP display, palette and sound
+    mega: MegaChipState,
+    // CHIP-8X colours
+    chip8x: Chip8XState,
+    // COSMAC VIP CPU, video and keypad
+    vip: VipState,
+    // The first 0nnn machine code problem the frontend hasn't been told about yet
+    machine_code_error: Option<MachineCodeError>,
+    // Recording of the presented frames
+    video: Option<VideoRecording>,
     // For the rando instruction
     rand_hasher: DefaultHasher
 }
 
 impl Chip8 {
     pub fn new(target: Target, clock: u32, rom: Vec<u8>) -> Chip8 {
+        let _mem_size = target.mem_size();
+        let _start_address = target.start_address();
+        let _frame_rate = Quirks::for_target(&target).frame_rate();
+        let _start = Instant::now();
+        let _frame_pixels = WIDTH * target.frame_height();
+        let _font = target.default_font().bytes();
         let mut chip8 = Chip8 {
+            quirks: Quirks::for_target(&target),
             target,
-            clock,
+            clock: clock as f32,
+            clock_hz: 0.0,
+            clock_carry: 0.0,
+            frame_budget: clock,
+            timing: Timing::default(),
+            frame_rate: _frame_rate,
             remaining: clock,
+            timer_cycles: 0.0,
+            host_pacing: false,
+            host_clock: Box::new(move || _start.elapsed()),
+            last_host_frame: None,
+            host_time: 0.0,
             r_v: [0; 16],
             r_i: 0,
-            r_pc: 0x200,
+            r_pc: _start_address,
             r_sp: 0,
             r_delay: 0,
             r_audio: 0,
             stack: [0; 16],
             rom: rom.clone(),
-            mem: [0; 0x10000],
+            mem: vec![0; _mem_size],
             halting: false,
             prev_op: 0,
             enabled_planes: 0b01,
             high_res: false,
-            active_planes: [
-                [0; HEIGHT],
-                [0; HEIGHT]
-            ],
-            buffer_planes: [
-                [0; HEIGHT],
-                [0; HEIGHT]
-            ],
-            prev_keys: [false; 16],
-            curr_keys: [false; 16],
+            two_page: false,
+            active_planes: [[0; HEIGHT]; PLANE_COUNT],
+            buffer_planes: [[0; HEIGHT]; PLANE_COUNT],
+            prev_keys: [false; 32],
+            curr_keys: [false; 32],
             num_output_channels: 0, // This is set by the frontend before emulation starts
             seconds_per_output_sample: 0.0, // This is set by the frontend before emulation starts
-            seconds_per_instruction: 1.0 / (FRAME_RATE * clock as f32),
+            seconds_per_cycle: 1.0 / (_frame_rate * clock as f32),
             audio_time: 0.0,
             audio_buffer: 0x0000FFFF0000FFFF0000FFFF0000FFFF, // Arbitrary pattern for non-XO buzzer
             audio_frequency: 4000.0,
             audio_oscillator: 0.0,
             sample_queue: VecDeque::new(),
+            audio: AudioState::new(),
+            palette: Palette::default().colors(),
+            font: _font,
+            custom_font: false,
+            persistence: PersistenceState::new(_frame_pixels),
+            mega: MegaChipState::new(),
+            chip8x: Chip8XState::new(),
+            vip: VipState::new(Vec::new()),
+            machine_code_error: None,
+            video: None,
             rand_hasher: RandomState::new().build_hasher()
         };
 
-        // Load fonts into memory
-        chip8.mem[..SMALL_FONT_SET.len()].copy_from_slice(&SMALL_FONT_SET); // 5-byte font
-        chip8.mem[SMALL_FONT_SET.len()..SMALL_FONT_SET.len() + BIG_FONT_SET.len()].copy_from_slice(&BIG_FONT_SET); // 10-byte font
+        chip8.load_fonts();
 
-        // Load ROM into memory
-        let mut i = 0x200; // ROM starts at 0x200 in memory
-        for byte in rom.into_iter() {
-            chip8.mem[i] = byte;
-            i += 1;
-        }
+        chip8.copy_rom();
 
         chip8
     }
 
+    // Load ROM into memory, anything past the end of memory is left out
+    fn copy_rom(&mut self) {
+        let _start = self.target.start_address();
+        let _len = self.rom.len().min(self.mem.len() - _start);
+        self.mem[_start .. _start + _len].copy_from_slice(&self.rom[.. _len]);
+    }
+
     pub fn reset(&mut self) {
-        self.remaining = self.clock;
+        self.clock_carry = 0.0;
+        self.remaining = self.next_frame_budget();
+        self.timer_cycles = 0.0;
         self.r_v = [0; 16];
         self.r_i = 0;
-        self.r_pc = 0x200;
+        self.r_pc = self.target.start_address();
         self.r_sp = 0;
         self.r_delay = 0;
         self.r_audio = 0;
         self.stack = [0; 16];
-        self.mem = [0; 0x10000];
+        self.mem = vec![0; self.target.mem_size()];
+        self.load_fonts();
         self.halting = false;
         self.prev_op = 0;
         self.enabled_planes = 0b01;
         self.high_res = false;
-        self.active_planes = [
-            [0; HEIGHT],
-            [0; HEIGHT]
-        ];
-        self.buffer_planes = [
-            [0; HEIGHT],
-            [0; HEIGHT]
-        ];
-        self.prev_keys = [false; 16];
-        self.curr_keys = [false; 16];
+        self.two_page = false;
+        self.active_planes = [[0; HEIGHT]; PLANE_COUNT];
+        self.buffer_planes = [[0; HEIGHT]; PLANE_COUNT];
+        self.prev_keys = [false; 32];
+        self.curr_keys = [false; 32];
         self.audio_time = 0.0;
         self.audio_buffer = 0x0000FFFF0000FFFF0000FFFF0000FFFF; // Arbitrary pattern for non-XO buzzer
         self.audio_frequency = 4000.0;
         self.audio_oscillator = 0.0;
         self.sample_queue = VecDeque::new();
+        self.audio.clear();
+        self.persistence.clear();
+        self.mega = MegaChipState::new();
+        self.chip8x = Chip8XState::new();
+        self.vip = VipState::new(std::mem::take(&mut self.vip.interpreter));
+        self.machine_code_error = None;
         self.rand_hasher = RandomState::new().build_hasher();
 
-        // Load ROM into memory
-        let mut i = 0x200; // ROM starts at 0x200 in memory
-        for byte in self.rom.clone().into_iter() {
-            self.mem[i] = byte;
-            i += 1;
+        self.copy_rom();
+        if self.target == Target::CosmacVip {
+            self.load_vip_interpreter();
         }
     }
 
+    // Set the clock in instructions per frame. This takes effect from the next frame, the current one isn't restarted.
     pub fn set_clock(&mut self, clock: u32) {
-        self.clock = clock;
-        self.seconds_per_instruction = 1.0 / (FRAME_RATE * clock as f32);
+        self.clock = clock as f32;
+        self.clock_hz = 0.0;
+        self.update_seconds_per_cycle();
+    }
+
+    // Set the clock in instructions per second, leftover fractions of an instruction carry over to the next frame
+    pub fn set_clock_hz(&mut self, hz: f32) {
+        self.clock = hz / self.frame_rate;
+        self.clock_hz = hz;
+        self.update_seconds_per_cycle();
+    }
+
+    pub fn set_timing(&mut self, timing: Timing) {
+        self.timing = timing;
+        // Start the frame over, the remaining count is in different units now
+        self.clock_carry = 0.0;
+        self.remaining = self.next_frame_budget();
+        self.timer_cycles = 0.0;
+        self.update_seconds_per_cycle();
+    }
+
+    // The budget for each frame on average, in instructions or VIP machine cycles
+    fn frame_cycles(&self) -> f32 {
+        match self.timing {
+            Timing::Instructions => self.clock,
+            Timing::Vip => vip::CYCLE_BUDGET as f32
+        }
+    }
+
+    // Start the budget for a new frame, keeping the fraction that doesn't fit for later frames
+    fn next_frame_budget(&mut self) -> u32 {
+        let _total = self.frame_cycles() + self.clock_carry;
+        self.frame_budget = _total as u32;
+        self.clock_carry = _total - self.frame_budget as f32;
+        self.frame_budget
+    }
+
+    // Start the next frame, the timers are decremented once per frame
+    fn end_frame(&mut self) {
+        self.remaining = self.next_frame_budget();
+
+        // Copy the active planes over to the buffer planes
+        self.present_frame();
+        self.capture_frame();
+    }
+
+    // Decrement the timers for every frame's worth of cycles that has been run.
+    // This goes by emulated time, so the timers run at the right speed however often the frontend calls the core.
+    fn run_timers(&mut self, cycles: u32) {
+        self.timer_cycles += cycles as f32;
+        let _cycles_per_tick = self.frame_cycles();
+        while _cycles_per_tick > 0.0 && self.timer_cycles >= _cycles_per_tick {
+            self.timer_cycles -= _cycles_per_tick;
+            if self.r_delay > 0 {
+                self.r_delay -= 1;
+            }
+            if self.r_audio > 0 {
+                self.r_audio -= 1;
+            }
+        }
+    }
+
+    // Pace run_frame by real time, for frontends that call it at the host's refresh rate
+    pub fn set_host_pacing(&mut self, host_pacing: bool) {
+        self.host_pacing = host_pacing;
+        self.last_host_frame = None;
+        self.host_time = 0.0;
+    }
+
+    // Replace the clock used for host pacing, which returns the time since any fixed point
+    pub fn set_host_clock(&mut self, clock: Box<dyn Fn() -> Duration + Send>) {
+        self.host_clock = clock;
+        self.last_host_frame = None;
+        self.host_time = 0.0;
+    }
+
+    // The number of frames to run for the real time since the last call, at most MAX_HOST_FRAMES after a stall.
+    // The frame count is rounded rather than truncated, so when the host's refresh rate is close to the frame rate,
+    // jitter in when it calls doesn't make frames alternate between 0 and 2.
+    fn host_frames(&mut self) -> u32 {
+        let _now = (self.host_clock)();
+        let _elapsed = match self.last_host_frame {
+            Some(last) => _now.saturating_sub(last).as_secs_f32().min(MAX_HOST_FRAMES as f32 / self.frame_rate),
+            None => 1.0 / self.frame_rate
+        };
+        self.last_host_frame = Some(_now);
+        self.host_time += _elapsed;
+        let _frames = (self.host_time * self.frame_rate).round() as u32;
+        self.host_time -= _frames as f32 / self.frame_rate;
+        _frames
+    }
+
+    fn update_seconds_per_cycle(&mut self) {
+        self.seconds_per_cycle = 1.0 / (self.frame_rate * self.frame_cycles());
+    }
+
+    pub fn get_frame_rate(&self) -> f32 {
+        self.frame_rate
+    }
+
+    pub fn set_frame_rate(&mut self, frame_rate: f32) {
+        self.frame_rate = frame_rate;
+        if self.clock_hz > 0.0 {
+            self.clock = self.clock_hz / frame_rate;
+        }
+        self.update_seconds_per_cycle();
     }
 
     pub fn set_target(&mut self, target: Target) {
+        self.quirks = Quirks::for_target(&target);
+        self.mem.resize(target.mem_size(), 0);
+        self.persistence.resize(WIDTH * target.frame_height());
+        self.set_frame_rate(self.quirks.frame_rate());
+        if !self.custom_font {
+            self.font = target.default_font().bytes();
+        }
         self.target = target;
+        self.load_fonts();
+    }
+
+    // Replace the font with the small font followed by the big font, like FontSet::bytes().
+    // Custom fonts can be shorter, only the characters they include are replaced.
+    pub fn set_font(&mut self, font: &[u8]) {
+        let _len = font.len().min(FONT_SIZE);
+        self.font[.. _len].copy_from_slice(&font[.. _len]);
+        self.custom_font = true;
+        self.load_fonts();
+    }
+
+    // Go back to the target's own font after set_font
+    pub fn set_default_font(&mut self) {
+        self.font = self.target.default_font().bytes();
+        self.custom_font = false;
+        self.load_fonts();
+    }
+
+    // Load the font into memory at the target's font address. The COSMAC VIP's interpreter reads its font from the
+    // monitor ROM, and 0000 is where the interpreter goes, so nothing is loaded there.
+    fn load_fonts(&mut self) {
+        if self.target == Target::CosmacVip {
+            return;
+        }
+        let _start = self.target.font_address();
+        self.mem[_start .. _start + FONT_SIZE].copy_from_slice(&self.font);
+    }
+
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+        self.set_frame_rate(quirks.frame_rate());
     }
 
     pub fn load_rom(&mut self, rom: Vec<u8>) {
         self.rom = rom;
     }
+
+    pub fn set_palette(&mut self, palette: [[u8; 4]; 16]) {
+        self.palette = palette;
+    }
 }
 
 impl Core for Chip8 {
     fn get_width(&self) -> usize {
-        WIDTH
+        match self.target {
+            Target::MegaChip => MEGA_WIDTH,
+            _ => WIDTH
+        }
     }
 
     fn get_height(&self) -> usize {
-        HEIGHT
+        match self.target {
+            Target::MegaChip => MEGA_HEIGHT,
+            _ => self.target.frame_height()
+        }
     }
 
     fn set_num_output_channels(&mut self, value: usize) {
@@ -244,7 +735,15 @@ impl Core for Chip8 {
     }
 
     fn run_inst(&mut self) {
-        self.remaining -= 1;
+        if self.target == Target::CosmacVip {
+            self.run_vip_inst();
+            return;
+        }
+        if self.remaining == 0 {
+            // The clock is slow enough that there's no instruction in this frame
+            self.end_frame();
+            return;
+        }
 
         // Get opcode
         let mut op = ((self.mem[self.r_pc] as u16) << 8) | self.mem[self.r_pc + 1] as u16;
@@ -254,11 +753,19 @@ impl Core for Chip8 {
             self.r_pc += 2;
         }
 
+        let _cycles = match self.timing {
+            Timing::Instructions => 1,
+            // A sprite waiting for the display interrupt uses up the rest of the frame
+            Timing::Vip if self.halting && op & 0xF000 == 0xD000 => self.remaining,
+            Timing::Vip => self.vip_instruction_cycles(op)
+        };
+        self.remaining = self.remaining.saturating_sub(_cycles);
+
         // F000 is a 4-byte instruction, so if we need to skip an instruction and PC is on F000,
         // we should skip 4 bytes instead of 2.
         let _next_op = ((self.mem[self.r_pc] as u16) << 8) | self.mem[self.r_pc + 1] as u16;
         let mut skip_count: usize = 2;
-        if _next_op == 0xF000 {
+        if _next_op == 0xF000 || self.target == Target::MegaChip && _next_op & 0xFF00 == 0x0100 {
             skip_count = 4;
         }
 
@@ -270,6 +777,16 @@ impl Core for Chip8 {
         let _nnn = (op & 0xFFF) as usize;
 
         'opcodes: {
+            // MEGA-CHIP extensions, see megachip/mod.rs
+            if self.target == Target::MegaChip && self.run_mega_opcode(op) {
+                break 'opcodes;
+            }
+
+            // CHIP-8X extensions, see chip8x/mod.rs
+            if self.target == Target::Chip8X && self.run_chip8x_opcode(op, skip_count) {
+                break 'opcodes;
+            }
+
             // 0-nibble param opcodes
             let mut opcode_matched = true;
             match op & 0xFFFF {
@@ -291,7 +808,7 @@ impl Core for Chip8 {
                     self.r_sp -= 1;
                     self.r_pc = self.stack[self.r_sp] as usize;
                 }
-                0x00FB if self.target != Target::Chip => {
+                0x00FB if self.target.has_scroll_opcodes() => {
                     // 00FB
                     // Scroll screen content right four pixels, in XO-CHIP only selected bit planes are scrolled
                     for p in 0 .. PLANE_COUNT {
@@ -303,7 +820,7 @@ impl Core for Chip8 {
                         }
                     }
                 }
-                0x00FC if self.target != Target::Chip => {
+                0x00FC if self.target.has_scroll_opcodes() => {
                     // 00FC
                     // Scroll screen content left four pixels, in XO-CHIP only selected bit planes are scrolled
                     for p in 0 .. PLANE_COUNT {
@@ -315,16 +832,16 @@ impl Core for Chip8 {
                         }
                     }
                 }
-                0x00FD if self.target != Target::Chip => {
+                0x00FD if self.target.has_super_opcodes() => {
                     // 00FD - EXIT
                     // Exit the interpreter.
                     // This used to return from main, but I'll just leave it for now.
                 }
-                0x00FE if self.target != Target::Chip => {
+                0x00FE if self.target.has_super_opcodes() => {
                     // 00FE - LOW
                     // Disable high-resolution mode.
                     self.high_res = false;
-                    if self.target != Target::SuperLegacy {
+                    if !self.target.has_legacy_display() {
                         for p in 0 .. PLANE_COUNT {
                             for i in 0 .. HEIGHT {
                                 self.active_planes[p][i] = 0;
@@ -332,11 +849,11 @@ impl Core for Chip8 {
                         }
                     }
                 }
-                0x00FF if self.target != Target::Chip => {
+                0x00FF if self.target.has_super_opcodes() => {
                     // 00FF - HIGH
                     // Enable high-resolution mode.
                     self.high_res = true;
-                    if self.target != Target::SuperLegacy {
+                    if !self.target.has_legacy_display() {
                         for p in 0 .. PLANE_COUNT {
                             for i in 0 .. HEIGHT {
                                 self.active_planes[p][i] = 0;
@@ -344,6 +861,11 @@ impl Core for Chip8 {
                         }
                     }
                 }
+                0x0230 if self.target == Target::HiresChip => {
+                    // 0230
+                    // Clear the display in HIRES CHIP-8.
+                    self.active_planes = [[0; HEIGHT]; PLANE_COUNT];
+                }
                 0xF000 if self.target == Target::XO => {
                     // F000
                     // Assign next 16 bit word to I, and set PC behind it. This is a four byte instruction.
@@ -368,10 +890,10 @@ impl Core for Chip8 {
             // 1-nibble param opcodes, but the param is the least significant nibble
             opcode_matched = true;
             match op & 0xFFF0 {
-                0x00C0 if self.target != Target::Chip => {
+                0x00C0 if self.target.has_scroll_opcodes() => {
                     // 00Cn
                     // Scroll screen content down N pixels, in XO-CHIP only selected bit planes are scrolled
-                    let _count = _n >> (self.target == Target::SuperLegacy && !self.high_res) as u8;
+                    let _count = _n >> (self.target.has_legacy_display() && !self.high_res) as u8;
                     for p in 0 .. PLANE_COUNT {
                         if (self.enabled_planes >> p) & 1 == 0 {
                             continue;
@@ -427,7 +949,9 @@ impl Core for Chip8 {
                 0xF001 if self.target == Target::XO => {
                     // Fx01
                     // Select bit planes to draw on to x (not vX) when drawing with Dxy0/Dxyn
-                    self.enabled_planes = (_x & 0b11) as u8;
+                    // With the four plane extension, all 4 bits of x are used.
+                    let _mask = if self.quirks.four_planes { 0b1111 } else { 0b11 };
+                    self.enabled_planes = (_x & _mask) as u8;
                 }
                 0xF007 => {
                     // Fx07 - LD Vx, DT
@@ -438,9 +962,11 @@ impl Core for Chip8 {
                     // Fx0A - LD Vx, K
                     // Wait for a key press, store the value of the key in Vx.
                     self.halting = true;
-                    // I guess I'll just grab the first key that releases between previous and current
+                    // I guess I'll just grab the first key that releases between previous and current.
+                    // CHIPOS on the DREAM 6800 doesn't wait for the key to be released.
+                    let _on_press = self.target == Target::Dream6800;
                     for i in 0 ..= 0xFusize {
-                        if self.prev_keys[i] && !self.curr_keys[i] {
+                        if self.prev_keys[i] != self.curr_keys[i] && self.curr_keys[i] == _on_press {
                             self.halting = false;
                             self.r_v[_x] = i as u8;
                             break;
@@ -465,12 +991,12 @@ impl Core for Chip8 {
                 0xF029 => {
                     // Fx29 - LD F, Vx
                     // Set I = location of 5-line sprite for digit Vx.
-                    self.r_i = (self.r_v[_x] & 0xF) as usize * 5;
+                    self.r_i = self.target.font_address() + (self.r_v[_x] & 0xF) as usize * 5;
                 }
-                0xF030 if self.target != Target::Chip => {
+                0xF030 if self.target.has_super_opcodes() => {
                     // Fx30
                     // Set I = location of 10-line sprite for digit Vx.
-                    self.r_i = SMALL_FONT_SET.len() + (self.r_v[_x] & 0xF) as usize * 10;
+                    self.r_i = self.target.font_address() + SMALL_FONT_SIZE + (self.r_v[_x] & 0xF) as usize * 10;
                 }
                 0xF033 => {
                     // Fx33 - LD B, Vx
@@ -490,8 +1016,8 @@ impl Core for Chip8 {
                     for i in 0 ..= _x {
                         self.mem[self.r_i + i] = self.r_v[i];
                     }
-                    if self.target == Target::Chip || self.target == Target::XO {
-                        self.r_i += _x + 1;
+                    if self.quirks.memory_increment {
+                        self.r_i += _x + !self.quirks.memory_increment_by_x as usize;
                     }
                 }
                 0xF065 => {
@@ -500,8 +1026,8 @@ impl Core for Chip8 {
                     for i in 0 ..= _x {
                         self.r_v[i] = self.mem[self.r_i + i];
                     }
-                    if self.target == Target::Chip || self.target == Target::XO {
-                        self.r_i += _x + 1;
+                    if self.quirks.memory_increment {
+                        self.r_i += _x + !self.quirks.memory_increment_by_x as usize;
                     }
                 }
                 _ => opcode_matched = false
@@ -543,7 +1069,7 @@ impl Core for Chip8 {
                     // 8xy1 - OR Vx, Vy
                     // Set Vx = Vx OR Vy.
                     self.r_v[_x] |= self.r_v[_y];
-                    if self.target == Target::Chip {
+                    if self.quirks.vf_reset {
                         self.r_v[0xF] = 0;
                     }
                 }
@@ -551,7 +1077,7 @@ impl Core for Chip8 {
                     // 8xy2 - AND Vx, Vy
                     // Set Vx = Vx AND Vy.
                     self.r_v[_x] &= self.r_v[_y];
-                    if self.target == Target::Chip {
+                    if self.quirks.vf_reset {
                         self.r_v[0xF] = 0;
                     }
                 }
@@ -559,7 +1085,7 @@ impl Core for Chip8 {
                     // 8xy3 - XOR Vx, Vy
                     // Set Vx = Vx XOR Vy.
                     self.r_v[_x] ^= self.r_v[_y];
-                    if self.target == Target::Chip {
+                    if self.quirks.vf_reset {
                         self.r_v[0xF] = 0;
                     }
                 }
@@ -585,7 +1111,7 @@ impl Core for Chip8 {
                     // 8xy6 - SHR Vx {, Vy}
                     // Set Vx = Vx SHR 1.
                     let mut prev = self.r_v[_y];
-                    if self.target == Target::SuperLegacy || self.target == Target::SuperModern {
+                    if self.quirks.shift {
                         prev = self.r_v[_x];
                     }
                     self.r_v[_x] = prev >> 1;
@@ -606,7 +1132,7 @@ impl Core for Chip8 {
                     // 8xyE - SHL Vx {, Vy}
                     // Set Vx = Vx SHL 1.
                     let mut prev = self.r_v[_y];
-                    if self.target == Target::SuperLegacy || self.target == Target::SuperModern {
+                    if self.quirks.shift {
                         prev = self.r_v[_x];
                     }
                     self.r_v[_x] = prev << 1;
@@ -623,7 +1149,13 @@ impl Core for Chip8 {
                 0x1000 => {
                     // 1nnn - JP addr
                     // Jump to location nnn.
-                    self.r_pc = _nnn;
+                    if self.target == Target::HiresChip && HIRES_ENTRIES.contains(&_nnn) && self.r_pc == self.target.start_address() + 2 {
+                        // 1244 or 1260 at the start of the ROM enables HIRES CHIP-8 mode, skip the interpreter patch
+                        self.two_page = true;
+                        self.r_pc = HIRES_START;
+                    } else {
+                        self.r_pc = _nnn;
+                    }
                 }
                 0x2000 => {
                     // 2nnn - CALL addr
@@ -673,7 +1205,7 @@ impl Core for Chip8 {
                     // Bnnn - JP V0, addr / Bxnn - JP Vx, addr
                     // Jump to location nnn + V0, or xnn + Vx on super.
                     let mut loc = self.r_v[0] as usize;
-                    if self.target == Target::SuperLegacy || self.target == Target::SuperModern {
+                    if self.quirks.jump {
                         let _i = (_nnn & 0xF00) >> 8;
                         loc = self.r_v[_i] as usize;
                     }
@@ -690,42 +1222,41 @@ impl Core for Chip8 {
                     // Display n-byte sprite starting at memory location I at (Vx, Vy), set VF = collision.
                     // Sprites are 8 pixels (8 bits/1 byte) wide and from 1 to 15 pixels in height,
                     // So each byte is one row of the sprite.
-                    // Draws a 16x16 sprite if n=0 and platform is not CHIP-8. (8x16 on super legacy in low-res mode)
+                    // Draws a 16x16 sprite if n=0 on SUPER-CHIP and later. (8x16 on super legacy in low-res mode, nothing on CHIP-48)
                     self.halting = true;
-                    if self.remaining == 0
-                        || self.target == Target::SuperModern
-                        || self.target == Target::SuperLegacy && self.high_res
-                        || self.target == Target::XO {
+                    if self.remaining == 0 || !self.quirks.display_wait || self.high_res {
                         self.halting = false;
-                        let mut plane_offset = -1;
+                        let _x_mod = WIDTH >> !self.high_res as u8;
+                        let _y_mod = if self.high_res || self.two_page { HEIGHT } else { self.target.low_res_height() };
+                        let _x_coord = self.r_v[_x] as usize % _x_mod;
+                        let _y_coord = self.r_v[_y] as usize % _y_mod;
+                        let mut sprite_height = (op & 0xF) as usize;
+                        let mut sprite_width = 8_usize;
+                        if self.target.has_super_opcodes() && sprite_height == 0 {
+                            sprite_height = 16;
+                            if !self.target.has_legacy_display() || self.high_res {
+                                sprite_width = 16;
+                            }
+                        }
+                        // In XO-CHIP, each selected plane gets its own sprite data, one after the other starting at I,
+                        // and a collision on any of the planes sets VF (like Octo).
+                        let mut plane_offset = 0;
+                        let mut unset = false;
+                        let mut collided_rows = 0;
                         for p in 0 .. PLANE_COUNT {
                             if (self.enabled_planes >> p) & 1 == 0 {
                                 continue;
                             }
-                            plane_offset += 1;
-                            let _x_mod = WIDTH >> !self.high_res as u8;
-                            let _y_mod = HEIGHT >> !self.high_res as u8;
-                            let _x_coord = self.r_v[_x] as usize % _x_mod;
-                            let _y_coord = self.r_v[_y] as usize % _y_mod;
-                            let mut sprite_height = (op & 0xF) as usize;
-                            let mut sprite_width = 8 as usize;
-                            if self.target != Target::Chip && sprite_height == 0 {
-                                sprite_height = 16;
-                                if self.target != Target::SuperLegacy || self.high_res {
-                                    sprite_width = 16;
-                                }
-                            }
-                            let mut unset = false;
                             for i in 0 .. sprite_height {
                                 let mut row_i = _y_coord + i;
                                 if row_i >= _y_mod {
-                                    if self.target != Target::XO {
+                                    if !self.quirks.wrap {
+                                        collided_rows += 1;
                                         continue;
                                     }
                                     row_i %= _y_mod;
                                 }
-                                // plane_offset will be non-negative at this point, so casting to usize is fine
-                                let _base_addr = (sprite_width >> 3) * (i + plane_offset as usize * sprite_height) + self.r_i;
+                                let _base_addr = (sprite_width >> 3) * (i + plane_offset * sprite_height) + self.r_i;
                                 let mut sprite_row = self.mem[_base_addr] as u128;
                                 if sprite_width == 16 {
                                     sprite_row = (sprite_row << 8) | self.mem[_base_addr + 1] as u128;
@@ -737,66 +1268,56 @@ impl Core for Chip8 {
                                 } else {
                                     self.active_planes[p][row_i] ^= sprite_row << (_shift - (sprite_width - 1));
                                 }
-                                if self.target == Target::XO && _x_coord > _x_mod - sprite_width {
+                                if self.quirks.wrap && _x_coord > _x_mod - sprite_width {
                                     self.active_planes[p][row_i] ^= sprite_row.rotate_right((_x_coord - (_x_mod - sprite_width)) as u32) & !0u128 << 112;
                                 }
                                 if !self.high_res {
                                     self.active_planes[p][row_i] &= !0u128 << 64;
                                 }
-                                unset = unset || (!self.active_planes[p][row_i] & _curr) > 0;
+                                let _row_unset = (!self.active_planes[p][row_i] & _curr) > 0;
+                                unset = unset || _row_unset;
+                                collided_rows += _row_unset as u8;
                             }
-                            self.r_v[0xF] = unset as u8;
+                            plane_offset += 1;
                         }
+                        self.r_v[0xF] = unset as u8;
+                        if self.quirks.collision_rows && self.high_res {
+                            self.r_v[0xF] = collided_rows;
+                        }
+                        self.sprite_drawn(unset);
                     }
                 }
+                0x0000 if self.target.has_machine_code() => {
+                    // 0nnn - SYS addr
+                    // Run the CDP1802 machine code subroutine at nnn, see machine_code/mod.rs
+                    self.run_machine_code(_nnn);
+                }
                 _ => panic!("Unimplemented opcode 0x{:0x}", op)
             }
         }
 
         self.prev_op = op;
 
-        self.audio_time += self.seconds_per_instruction;
-        if self.audio_time >= self.seconds_per_output_sample {
-            self.audio_time -= self.seconds_per_output_sample;
-            self.audio_oscillator = (self.audio_oscillator + self.seconds_per_output_sample * self.audio_frequency) % 128.0;
-            for _i in 0..self.num_output_channels {
-                self.sample_queue.push_back(match self.r_audio {
-                    0 => 0.0,
-                    _ => ((self.audio_buffer >> self.audio_oscillator as u32) & 1) as f32
-                });
-            }
-        }
+        self.run_audio(self.seconds_per_cycle * _cycles as f32);
+        self.run_timers(_cycles);
 
         if self.remaining == 0 {
-            // This frame is over, reset the remaining instruction count
-            self.remaining = self.clock;
-
-            // Decrement timers.
-            // They decrement at 60hz, so because fps is around 60, just decrement once per frame.
-            if self.r_delay > 0 {
-                self.r_delay -= 1;
-            }
-            if self.r_audio > 0 {
-                self.r_audio -= 1;
-            }
-            if self.r_audio == 0 {
-                self.audio_oscillator = 0.0;
-            }
-
-            // Copy the active planes over to the buffer planes
-            for _p in 0 .. PLANE_COUNT {
-                for _i in 0 .. HEIGHT {
-                    self.buffer_planes[_p][_i] = self.active_planes[_p][_i];
-                }
-            }
+            self.end_frame();
         }
     }
 
     fn run_frame(&mut self) {
-        loop {
-            self.run_inst();
-            if self.remaining == self.clock {
-                break;
+        let _frames = if self.host_pacing { self.host_frames() } else { 1 };
+        for _i in 0 .. _frames {
+            if self.target == Target::CosmacVip {
+                self.run_vip_frame();
+                continue;
+            }
+            loop {
+                self.run_inst();
+                if self.remaining == self.frame_budget {
+                    break;
+                }
             }
         }
     }
@@ -806,10 +1327,7 @@ impl Core for Chip8 {
     }
 
     fn get_sample(&mut self) -> f32 {
-        match self.sample_queue.pop_front() {
-            Some(sample) => sample * 0.15,
-            None => 0.0
-        }
+        self.sample_queue.pop_front().unwrap_or(0.0)
     }
 
     fn press_key(&mut self, key_index: usize) {
@@ -827,25 +1345,16 @@ impl Core for Chip8 {
     }
 
     fn draw(&self, frame: &mut [u8]) {
+        if self.target == Target::MegaChip {
+            self.draw_mega(frame);
+            return;
+        }
+        if self.target == Target::Chip8X {
+            self.draw_chip8x(frame);
+            return;
+        }
         for (i, pixel) in frame.chunks_exact_mut(4).enumerate() {
-            let x = WIDTH - 1 - (i % WIDTH >> !self.high_res as u8);
-            let y = i / WIDTH >> !self.high_res as u8;
-    
-            let _both = self.buffer_planes[0][y] & self.buffer_planes[1][y];
-            let _zero = self.buffer_planes[0][y] & !_both;
-            let _one = self.buffer_planes[1][y] & !_both;
-    
-            let rgba = if _both & (1 << x) > 0 {
-                [0x99, 0x66, 0x00, 0xff]
-            } else if _zero & (1 << x) > 0 {
-                [0xff, 0xcc, 0x00, 0xff]
-            } else if _one & (1 << x) > 0 {
-                [0xff, 0x66, 0x00, 0xff]
-            } else {
-                [0x66, 0x22, 0x00, 0xff]
-            };
-    
-            pixel.copy_from_slice(&rgba);
+            pixel.copy_from_slice(&self.pixel_color(i));
         }
     }
 }
@@ -861,7 +1370,8 @@ struct JsApi {
 impl JsApi {
     #[wasm_bindgen(constructor)]
     pub fn new(target: Target, clock: u32, rom: Vec<u8>, keymap: Keymap, sync_mode: SyncModes) -> JsApi {
-        let core = Chip8::new(target, clock, rom);
+        let mut core = Chip8::new(target, clock, rom);
+        core.set_host_pacing(true);
         let arc_core = Arc::new(Mutex::new(core));
         JsApi {
             frontend: Frontend::new(arc_core.clone(), keymap, sync_mode),
@@ -889,13 +1399,87 @@ impl JsApi {
         self.core.lock().unwrap().set_clock(clock);
     }
 
+    #[wasm_bindgen]
+    pub fn set_clock_hz(&self, hz: f32) {
+        self.core.lock().unwrap().set_clock_hz(hz);
+    }
+
+    #[wasm_bindgen]
+    pub fn set_volume(&self, volume: f32) {
+        self.core.lock().unwrap().set_volume(volume);
+    }
+
+    #[wasm_bindgen]
+    pub fn set_low_pass(&self, hz: f32) {
+        self.core.lock().unwrap().set_low_pass(hz);
+    }
+
+    #[wasm_bindgen]
+    pub fn start_audio_recording(&self) {
+        self.core.lock().unwrap().start_audio_recording();
+    }
+
+    #[wasm_bindgen]
+    pub fn start_video_recording(&self, format: VideoFormat) {
+        self.core.lock().unwrap().start_video_recording(format);
+    }
+
+    // Stop recording and return the GIF file or raw frames
+    #[wasm_bindgen]
+    pub fn stop_video_recording(&self) -> Vec<u8> {
+        self.core.lock().unwrap().stop_video_recording()
+    }
+
+    // Stop recording and return what was recorded as a WAV file
+    #[wasm_bindgen]
+    pub fn stop_audio_recording(&self) -> Vec<u8> {
+        let mut core = self.core.lock().unwrap();
+        let _samples = core.stop_audio_recording();
+        wav::encode_wav(&_samples, core.get_num_output_channels(), core.get_sample_rate())
+    }
+
+    #[wasm_bindgen]
+    pub fn set_timing(&self, timing: Timing) {
+        self.core.lock().unwrap().set_timing(timing);
+    }
+
     #[wasm_bindgen]
     pub fn set_target(&self, target: Target) {
         self.core.lock().unwrap().set_target(target);
     }
 
+    #[wasm_bindgen]
+    pub fn set_quirks(&self, quirks: Quirks) {
+        self.core.lock().unwrap().set_quirks(quirks);
+    }
+
     #[wasm_bindgen]
     pub fn load_rom(&self, rom: Vec<u8>) {
         self.core.lock().unwrap().load_rom(rom);
     }
+
+    #[wasm_bindgen]
+    pub fn set_persistence(&self, mode: Persistence) {
+        self.core.lock().unwrap().set_persistence(mode);
+    }
+
+    #[wasm_bindgen]
+    pub fn set_blend_frames(&self, frames: usize) {
+        self.core.lock().unwrap().set_blend_frames(frames);
+    }
+
+    #[wasm_bindgen]
+    pub fn set_font_set(&self, font: FontSet) {
+        self.core.lock().unwrap().set_font(&font.bytes());
+    }
+
+    #[wasm_bindgen]
+    pub fn set_font(&self, font: Vec<u8>) {
+        self.core.lock().unwrap().set_font(&font);
+    }
+
+    #[wasm_bindgen]
+    pub fn set_vip_interpreter(&self, interpreter: Vec<u8>) {
+        self.core.lock().unwrap().set_vip_interpreter(interpreter);
+    }
 }
@@ -0,0 +1,140 @@
+// Display persistence, to hide the flicker caused by games erasing and redrawing sprites with XOR every frame.
+
+use super::{Chip8, Target, HEIGHT, PLANE_COUNT, WIDTH};
+use clap::ValueEnum;
+use std::collections::VecDeque;
+use wasm_bindgen::prelude::*;
+
+// How much of the previous colour is kept each frame in phosphor mode when a pixel gets darker
+const PHOSPHOR_DECAY: f32 = 0.6;
+
+#[wasm_bindgen]
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq)]
+pub enum Persistence {
+    #[default]
+    Off, // Show each frame as-is
+    Blend, // Average the colours of the last N frames
+    Phosphor, // Pixels light up immediately but fade out over a few frames, like a CRT
+    DrawOnSet // Only update the screen after a sprite draw that didn't erase anything
+}
+
+// Display state that is only used for presenting frames, so it isn't part of save states
+pub struct PersistenceState {
+    pub mode: Persistence,
+    pub blend_frames: usize,
+    history: VecDeque<[[u128; HEIGHT]; PLANE_COUNT]>,
+    phosphor: Vec<[f32; 3]>,
+    // Whether a sprite was drawn during the current frame, for DrawOnSet
+    pub sprite_drawn: bool
+}
+
+impl PersistenceState {
+    // pixels is the size of the output frame
+    pub fn new(pixels: usize) -> PersistenceState {
+        PersistenceState {
+            mode: Persistence::default(),
+            blend_frames: 2,
+            history: VecDeque::new(),
+            phosphor: vec![[0.0; 3]; pixels],
+            sprite_drawn: false
+        }
+    }
+
+    pub fn resize(&mut self, pixels: usize) {
+        self.phosphor.resize(pixels, [0.0; 3]);
+    }
+
+    pub fn clear(&mut self) {
+        self.history.clear();
+        self.phosphor.fill([0.0; 3]);
+        self.sprite_drawn = false;
+    }
+}
+
+impl Chip8 {
+    pub fn set_persistence(&mut self, mode: Persistence) {
+        self.persistence.mode = mode;
+        self.persistence.clear();
+    }
+
+    pub fn set_blend_frames(&mut self, frames: usize) {
+        self.persistence.blend_frames = frames.max(1);
+    }
+
+    // Palette index of the pixel at index i of the output frame
+    pub(super) fn color_index(&self, planes: &[[u128; HEIGHT]; PLANE_COUNT], i: usize) -> usize {
+        if self.target == Target::CosmacVip {
+            return self.vip_color_index(planes, i);
+        }
+        let x = WIDTH - 1 - ((i % WIDTH) >> !self.high_res as u8);
+        let _rows = if self.high_res || self.two_page { HEIGHT } else { self.target.low_res_height() };
+        let y = (i / WIDTH) * _rows / self.target.frame_height();
+        let mut index = 0;
+        for (p, plane) in planes.iter().enumerate() {
+            index |= (((plane[y] >> x) & 1) as usize) << p;
+        }
+        index
+    }
+
+    // Colour of the pixel at index i of the output frame after persistence is applied
+    pub(super) fn pixel_color(&self, i: usize) -> [u8; 4] {
+        match self.persistence.mode {
+            Persistence::Off | Persistence::DrawOnSet => self.palette[self.color_index(&self.buffer_planes, i)],
+            Persistence::Blend => {
+                let mut sum = [0_u32; 3];
+                for planes in self.persistence.history.iter() {
+                    let color = self.palette[self.color_index(planes, i)];
+                    for c in 0 .. 3 {
+                        sum[c] += color[c] as u32;
+                    }
+                }
+                let count = self.persistence.history.len().max(1) as u32;
+                [(sum[0] / count) as u8, (sum[1] / count) as u8, (sum[2] / count) as u8, 0xff]
+            }
+            Persistence::Phosphor => {
+                let color = self.persistence.phosphor[i];
+                [color[0] as u8, color[1] as u8, color[2] as u8, 0xff]
+            }
+        }
+    }
+
+    // Copy the active planes to the buffer planes at the end of a frame and update the persistence state
+    pub(super) fn present_frame(&mut self) {
+        if self.persistence.mode != Persistence::DrawOnSet || !self.persistence.sprite_drawn {
+            self.buffer_planes = self.active_planes;
+        }
+        self.persistence.sprite_drawn = false;
+
+        match self.persistence.mode {
+            Persistence::Blend => {
+                self.persistence.history.push_back(self.buffer_planes);
+                while self.persistence.history.len() > self.persistence.blend_frames {
+                    self.persistence.history.pop_front();
+                }
+            }
+            Persistence::Phosphor => {
+                for i in 0 .. self.persistence.phosphor.len() {
+                    let target = self.palette[self.color_index(&self.buffer_planes, i)];
+                    let color = &mut self.persistence.phosphor[i];
+                    for c in 0 .. 3 {
+                        let target = target[c] as f32;
+                        color[c] = if color[c] > target {
+                            color[c] * PHOSPHOR_DECAY + target * (1.0 - PHOSPHOR_DECAY)
+                        } else {
+                            target
+                        };
+                    }
+                }
+            }
+            _ => ()
+        }
+    }
+
+    // Called after each sprite draw, in DrawOnSet mode a draw without collisions means the frame is complete
+    pub(super) fn sprite_drawn(&mut self, collision: bool) {
+        self.persistence.sprite_drawn = true;
+        if self.persistence.mode == Persistence::DrawOnSet && !collision {
+            self.buffer_planes = self.active_planes;
+        }
+    }
+}
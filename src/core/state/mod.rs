@@ -0,0 +1,333 @@
+// Save states, a flat little-endian dump of everything needed to resume emulation.
+// The ROM, target, clock and palette aren't included since they're part of the configuration, not the machine state.
+
+use super::{Chip8, Target};
+use super::megachip::Sound;
+use super::vip::{CYCLES_PER_FRAME, DMA_CYCLES_PER_LINE};
+
+// Bump this whenever the layout below changes so old states are rejected instead of loaded wrong
+const STATE_VERSION: u8 = 6;
+const STATE_MAGIC: &[u8; 4] = b"C8ST";
+
+struct StateWriter {
+    data: Vec<u8>
+}
+
+impl StateWriter {
+    fn write(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
+    }
+
+    fn write_u8(&mut self, value: u8) {
+        self.write(&[value]);
+    }
+
+    fn write_bool(&mut self, value: bool) {
+        self.write_u8(value as u8);
+    }
+
+    fn write_u16(&mut self, value: u16) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, value: u128) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_f32(&mut self, value: f32) {
+        self.write(&value.to_le_bytes());
+    }
+}
+
+struct StateReader<'a> {
+    data: &'a [u8],
+    pos: usize
+}
+
+impl StateReader<'_> {
+    fn read<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let bytes = self.data.get(self.pos .. self.pos + N)?;
+        self.pos += N;
+        bytes.try_into().ok()
+    }
+
+    fn read_slice(&mut self, len: usize) -> Option<&[u8]> {
+        let bytes = self.data.get(self.pos .. self.pos + len)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    fn read_u8(&mut self) -> Option<u8> {
+        Some(self.read::<1>()?[0])
+    }
+
+    fn read_bool(&mut self) -> Option<bool> {
+        Some(self.read_u8()? != 0)
+    }
+
+    fn read_u16(&mut self) -> Option<u16> {
+        Some(u16::from_le_bytes(self.read()?))
+    }
+
+    fn read_u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.read()?))
+    }
+
+    fn read_u128(&mut self) -> Option<u128> {
+        Some(u128::from_le_bytes(self.read()?))
+    }
+
+    fn read_f32(&mut self) -> Option<f32> {
+        Some(f32::from_le_bytes(self.read()?))
+    }
+}
+
+impl Chip8 {
+    // Serialize the machine state. The length is the same for every state, so it can be used as the state size.
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut writer = StateWriter { data: Vec::new() };
+        writer.write(STATE_MAGIC);
+        writer.write_u8(STATE_VERSION);
+        writer.write_u32(self.remaining);
+        writer.write(&self.r_v);
+        writer.write_u32(self.r_i as u32);
+        writer.write_u32(self.r_pc as u32);
+        writer.write_u8(self.r_sp as u8);
+        writer.write_u8(self.r_delay);
+        writer.write_u8(self.r_audio);
+        for value in self.stack {
+            writer.write_u16(value);
+        }
+        writer.write(&self.mem);
+        writer.write_bool(self.halting);
+        writer.write_u16(self.prev_op);
+        writer.write_u8(self.enabled_planes);
+        writer.write_bool(self.high_res);
+        writer.write_bool(self.two_page);
+        for planes in [&self.active_planes, &self.buffer_planes] {
+            for plane in planes {
+                for row in plane {
+                    writer.write_u128(*row);
+                }
+            }
+        }
+        for keys in [&self.prev_keys, &self.curr_keys] {
+            for key in keys {
+                writer.write_bool(*key);
+            }
+        }
+        writer.write_f32(self.audio_time);
+        writer.write_u128(self.audio_buffer);
+        writer.write_f32(self.audio_frequency);
+        writer.write_f32(self.audio_oscillator);
+        if self.target == Target::MegaChip {
+            self.write_mega_state(&mut writer);
+        }
+        if self.target == Target::CosmacVip {
+            self.write_vip_state(&mut writer);
+        }
+        if self.target == Target::Chip8X {
+            writer.write_u8(self.chip8x.background);
+            for row in self.chip8x.zones {
+                writer.write(&row);
+            }
+        }
+        writer.data
+    }
+
+    // Restore a state created by save_state. Returns false and leaves the machine untouched if the state is invalid.
+    // Only the machine state is read, so the configuration (target, clock, quirks, palette, persistence, audio settings,
+    // font and so on) and anything being recorded stay as they are.
+    pub fn load_state(&mut self, data: &[u8]) -> bool {
+        let mut reader = StateReader { data, pos: 0 };
+        if reader.read::<4>().as_ref() != Some(STATE_MAGIC) || reader.read_u8() != Some(STATE_VERSION) {
+            return false;
+        }
+        // Put the current state back if the new one turns out to be invalid
+        let _current = self.save_state();
+        let _timer_cycles = self.timer_cycles;
+        if self.read_state(&mut reader).is_none() || reader.pos != data.len() {
+            let mut reader = StateReader { data: &_current, pos: STATE_MAGIC.len() + 1 };
+            self.read_state(&mut reader).expect("the current state should be valid");
+            self.timer_cycles = _timer_cycles;
+            return false;
+        }
+
+        // The filters and display history were for the old state
+        self.audio.clear();
+        self.persistence.clear();
+        self.machine_code_error = None;
+        true
+    }
+
+    fn read_state(&mut self, reader: &mut StateReader) -> Option<()> {
+        self.remaining = reader.read_u32()?;
+        self.r_v = reader.read()?;
+        self.r_i = reader.read_u32()? as usize;
+        self.r_pc = reader.read_u32()? as usize;
+        self.r_sp = reader.read_u8()? as usize;
+        self.r_delay = reader.read_u8()?;
+        self.r_audio = reader.read_u8()?;
+        for value in self.stack.iter_mut() {
+            *value = reader.read_u16()?;
+        }
+        let mem = reader.read_slice(self.mem.len())?;
+        self.mem.copy_from_slice(mem);
+        self.halting = reader.read_bool()?;
+        self.prev_op = reader.read_u16()?;
+        self.enabled_planes = reader.read_u8()?;
+        self.high_res = reader.read_bool()?;
+        self.two_page = reader.read_bool()?;
+        for planes in [&mut self.active_planes, &mut self.buffer_planes] {
+            for row in planes.iter_mut().flatten() {
+                *row = reader.read_u128()?;
+            }
+        }
+        for keys in [&mut self.prev_keys, &mut self.curr_keys] {
+            for key in keys.iter_mut() {
+                *key = reader.read_bool()?;
+            }
+        }
+        self.audio_time = reader.read_f32()?;
+        self.audio_buffer = reader.read_u128()?;
+        self.audio_frequency = reader.read_f32()?;
+        self.audio_oscillator = reader.read_f32()?;
+        if self.target == Target::MegaChip {
+            self.read_mega_state(reader)?;
+        }
+        if self.target == Target::CosmacVip {
+            self.read_vip_state(reader)?;
+        }
+        if self.target == Target::Chip8X {
+            self.chip8x.background = reader.read_u8()? % 4;
+            for row in self.chip8x.zones.iter_mut() {
+                *row = reader.read()?;
+                row.iter_mut().for_each(|color| *color &= 0x7);
+            }
+        }
+
+        // Keep the registers in range so a corrupted state can't cause out of bounds accesses later
+        if self.r_sp > self.stack.len() || self.r_pc >= self.mem.len() - 1 || self.r_i >= self.mem.len() {
+            return None;
+        }
+        // The clock may have been changed since the state was saved
+        self.remaining = self.remaining.min(self.frame_budget);
+        // Keep the timers in step with the frame
+        self.timer_cycles = (self.frame_budget - self.remaining) as f32;
+        Some(())
+    }
+
+    // The MEGA-CHIP display is only saved for that target, since it's much bigger than everything else
+    fn write_mega_state(&self, writer: &mut StateWriter) {
+        let mega = &self.mega;
+        writer.write_bool(mega.enabled);
+        for color in mega.palette {
+            writer.write(&color);
+        }
+        writer.write_u32(mega.sprite_width as u32);
+        writer.write_u32(mega.sprite_height as u32);
+        writer.write_u8(mega.screen_alpha);
+        writer.write_u8(mega.collision_color);
+        writer.write_u8(mega.blend_mode.to_nibble());
+        writer.write(&mega.active_indices);
+        for colors in [&mega.active_colors, &mega.buffer_colors] {
+            for color in colors {
+                writer.write(color);
+            }
+        }
+        writer.write_bool(mega.sound.is_some());
+        let sound = mega.sound.unwrap_or(Sound { start: 0, length: 0, rate: 0.0, position: 0.0, looping: false });
+        writer.write_u32(sound.start as u32);
+        writer.write_u32(sound.length as u32);
+        writer.write_f32(sound.rate);
+        writer.write_f32(sound.position);
+        writer.write_bool(sound.looping);
+    }
+
+    fn write_vip_state(&self, writer: &mut StateWriter) {
+        let (cpu, io) = (&self.vip.cpu, &self.vip.io);
+        for value in cpu.r {
+            writer.write_u16(value);
+        }
+        writer.write_u8(((cpu.p as u8) << 4) | cpu.x as u8);
+        writer.write_u8(cpu.d);
+        writer.write_bool(cpu.df);
+        writer.write_u8(cpu.t);
+        writer.write_bool(cpu.ie);
+        writer.write_bool(cpu.q);
+        writer.write_bool(cpu.idle);
+        writer.write_bool(io.display_on);
+        writer.write_u32(io.cycle);
+        writer.write_u32(io.dma_pending);
+        writer.write_bool(io.interrupt_pending);
+        writer.write_u8(io.key_latch);
+        for line in io.lines {
+            writer.write(&line.to_le_bytes());
+        }
+    }
+
+    fn read_vip_state(&mut self, reader: &mut StateReader) -> Option<()> {
+        let (cpu, io) = (&mut self.vip.cpu, &mut self.vip.io);
+        for value in cpu.r.iter_mut() {
+            *value = reader.read_u16()?;
+        }
+        let _px = reader.read_u8()?;
+        cpu.p = (_px >> 4) as usize;
+        cpu.x = (_px & 0xF) as usize;
+        cpu.d = reader.read_u8()?;
+        cpu.df = reader.read_bool()?;
+        cpu.t = reader.read_u8()?;
+        cpu.ie = reader.read_bool()?;
+        cpu.q = reader.read_bool()?;
+        cpu.idle = reader.read_bool()?;
+        io.display_on = reader.read_bool()?;
+        io.cycle = reader.read_u32()?;
+        io.dma_pending = reader.read_u32()?;
+        io.interrupt_pending = reader.read_bool()?;
+        io.key_latch = reader.read_u8()? & 0xF;
+        for line in io.lines.iter_mut() {
+            *line = u64::from_le_bytes(reader.read()?);
+        }
+        if io.cycle >= CYCLES_PER_FRAME || io.dma_pending > DMA_CYCLES_PER_LINE {
+            return None;
+        }
+        Some(())
+    }
+
+    fn read_mega_state(&mut self, reader: &mut StateReader) -> Option<()> {
+        let mega = &mut self.mega;
+        mega.enabled = reader.read_bool()?;
+        for color in mega.palette.iter_mut() {
+            *color = reader.read()?;
+        }
+        mega.sprite_width = reader.read_u32()? as usize;
+        mega.sprite_height = reader.read_u32()? as usize;
+        mega.screen_alpha = reader.read_u8()?;
+        mega.collision_color = reader.read_u8()?;
+        mega.set_blend_mode(reader.read_u8()?);
+        let indices = reader.read_slice(mega.active_indices.len())?;
+        mega.active_indices.copy_from_slice(indices);
+        for colors in [&mut mega.active_colors, &mut mega.buffer_colors] {
+            for color in colors.iter_mut() {
+                *color = reader.read()?;
+            }
+        }
+        let playing = reader.read_bool()?;
+        let sound = Sound {
+            start: reader.read_u32()? as usize,
+            length: reader.read_u32()? as usize,
+            rate: reader.read_f32()?,
+            position: reader.read_f32()?,
+            looping: reader.read_bool()?
+        };
+        if playing && sound.start + sound.length > self.mem.len() {
+            return None;
+        }
+        mega.sound = if playing { Some(sound) } else { None };
+        Some(())
+    }
+}
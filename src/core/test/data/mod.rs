@@ -539,6 +539,140 @@ pub const QUIRKS_XO_PLANE: [u128; core::HEIGHT] = [
     0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
     0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
 ];
+// CHIP-48 run in the CHIP-8 mode and SUPER-CHIP 1.0 in the legacy SUPER-CHIP mode, so the quirks they differ in from
+// those platforms are crossed out: vF reset, memory, display wait, shifting and jumping for CHIP-48, memory for 1.0
+pub const QUIRKS_CHIP48_PLANE: [u128; core::HEIGHT] = [
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b01010111000001100111001101110111000000000011101110111000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b01010100000001010110011001100010000000000010101000100000000101000000000000000000000000000000000000000000000000000000000000000000,
+    0b01010110000001100100000101000010000000000010101100110000000010000000000000000000000000000000000000000000000000000000000000000000,
+    0b00100100000001010111011001110010000000000011101000100000000101000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b01110111011101110110010100000000000000000011101100000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b01110110011101010101010100000000000000000010101010000000000101000000000000000000000000000000000000000000000000000000000000000000,
+    0b01010100010101010110001000000000000000000010101010000000000010000000000000000000000000000000000000000000000000000000000000000000,
+    0b01010111010101110101001000000000000000000011101010000000000101000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b01100111001101100000010100100111011100000011101110111000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b01010010011001010000010101010010001000000010101000100000000101000000000000000000000000000000000000000000000000000000000000000000,
+    0b01010010000101100000011101110010001000000010101100110000000010000000000000000000000000000000000000000000000000000000000000000000,
+    0b01100111011001000010011101010111001000000011101000100000000101000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b01110100011101100110011101100011000000000011101100000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b01000100001001010101001001010100000000000010101010000000000101000000000000000000000000000000000000000000000000000000000000000000,
+    0b01000100001001100110001001010101000000000010101010000000000110000000000000000000000000000000000000000000000000000000000000000000,
+    0b01110111011101000100011101010011000000000011101010000000000100000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00110101011101110111011101100011000000000011101100000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b01100111001001000010001001010100000000000010101010000000000101000000000000000000000000000000000000000000000000000000000000000000,
+    0b00010101001001100010001001010101000000000010101010000000000010000000000000000000000000000000000000000000000000000000000000000000,
+    0b01100101011101000010011101010011000000000011101010000000000101000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00110101011101100111011000110000000000000011101100000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00010101011101010010010101000000000000000010101010000000000101000000000000000000000000000000000000000000000000000000000000000000,
+    0b00010101010101100010010101010000000000000010101010000000000010000000000000000000000000000000000000000000000000000000000000000000,
+    0b01100011010101000111010100110000000000000011101010000000000101000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+];
+pub const QUIRKS_SUPER10_PLANE: [u128; core::HEIGHT] = [
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b01010111000001100111001101110111000000000011101110111000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b01010100000001010110011001100010000000000010101000100000000101000000000000000000000000000000000000000000000000000000000000000000,
+    0b01010110000001100100000101000010000000000010101100110000000110000000000000000000000000000000000000000000000000000000000000000000,
+    0b00100100000001010111011001110010000000000011101000100000000100000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b01110111011101110110010100000000000000000011101100000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b01110110011101010101010100000000000000000010101010000000000101000000000000000000000000000000000000000000000000000000000000000000,
+    0b01010100010101010110001000000000000000000010101010000000000010000000000000000000000000000000000000000000000000000000000000000000,
+    0b01010111010101110101001000000000000000000011101010000000000101000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b01100111001101100000010100100111011100000010001100111001100000000000000000000000000000000000000000000000000000000000000000000000,
+    0b01010010011001010000010101010010001000000010001010110011000101000000000000000000000000000000000000000000000000000000000000000000,
+    0b01010010000101100000011101110010001000000010001100100000100110000000000000000000000000000000000000000000000000000000000000000000,
+    0b01100111011001000010011101010111001000000011101010111011000100000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b01110100011101100110011101100011000000000011001110111010100000000000000000000000000000000000000000000000000000000000000000000000,
+    0b01000100001001010101001001010100000000000011101010010011100101000000000000000000000000000000000000000000000000000000000000000000,
+    0b01000100001001100110001001010101000000000010101010010010100110000000000000000000000000000000000000000000000000000000000000000000,
+    0b01110111011101000100011101010011000000000011101110010010100100000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00110101011101110111011101100011000000000011101100000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b01100111001001000010001001010100000000000010101010000000000101000000000000000000000000000000000000000000000000000000000000000000,
+    0b00010101001001100010001001010101000000000010101010000000000110000000000000000000000000000000000000000000000000000000000000000000,
+    0b01100101011101000010011101010011000000000011101010000000000100000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00110101011101100111011000110000000000000011101100000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00010101011101010010010101000000000000000010101010000000000101000000000000000000000000000000000000000000000000000000000000000000,
+    0b00010101010101100010010101010000000000000010101010000000000110000000000000000000000000000000000000000000000000000000000000000000,
+    0b01100011010101000111010100110000000000000011101010000000000100000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+];
 
 pub const SCROLLING: &[u8] = include_bytes!("../../../../roms/8-scrolling.ch8");
 pub const SCROLLING_SUPER_MODERN_LOW_PLANE: [u128; core::HEIGHT] = [
@@ -871,3 +1005,275 @@ pub const SCROLLING_XO_HIGH_PLANE: [u128; core::HEIGHT] = [
     0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
     0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
 ];
+
+// The xo-planes ROMs are assembled from the .8o sources next to them. Their planes are generated by
+// tools/octo-planes.js, which draws sprites the way Octo's emulator does rather than with chippy's code.
+pub const XO_PLANES: &[u8] = include_bytes!("../../../../roms/xo-planes.ch8");
+pub const XO_PLANES_LOW_PLANE_0: [u128; core::HEIGHT] = [
+    0b11110000000100000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000000000,
+    0b10010000000100000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000000000,
+    0b10010000000100000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000000000,
+    0b11110000000100000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000100000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000100000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000100000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000100000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000100000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000100000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000100000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000100000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000100000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000000000,
+    0b11111111111100000000000000000000000000000000000000000000000011110000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b11111111111100000000000000000000000000000000000000000000000011110000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000100000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+];
+pub const XO_PLANES_LOW_PLANE_1: [u128; core::HEIGHT] = [
+    0b00001111010000000000000000000000000000000000000000000000000000100000000000000000000000000000000000000000000000000000000000000000,
+    0b00001001100000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000000,
+    0b10001000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b01001101000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00100100000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00011000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00011000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00100100000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b01000010000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b10000001000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000100000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000010000000000000000000000000000000000000000000000000000100000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000001000000000000000000000000000000000000000000000000001000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000100000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000100000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000001000000000000000000000000000000000000000000000000001000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+];
+
+pub const XO_PLANES_HIRES: &[u8] = include_bytes!("../../../../roms/xo-planes-hires.ch8");
+pub const XO_PLANES_HIGH_PLANE_0: [u128; core::HEIGHT] = [
+    0b11110001000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000010000000,
+    0b10010001000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000010000000,
+    0b10010001000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000010000000,
+    0b11110001000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000010000000,
+    0b00000001000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000010000000,
+    0b00000001000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000010000000,
+    0b00000001000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000010000000,
+    0b00000001000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000010000000,
+    0b00000001000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000010000000,
+    0b00000001000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000010000000,
+    0b00000001000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000010000000,
+    0b11111111000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000011111111,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b11111111000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000011111111,
+    0b00000001000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000010000000,
+    0b00000001000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000010000000,
+    0b00000001000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000010000000,
+];
+pub const XO_PLANES_HIGH_PLANE_1: [u128; core::HEIGHT] = [
+    0b00011111000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000001000,
+    0b00101001000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000100,
+    0b01001001000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000010,
+    0b10001111000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000001,
+    0b10000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000001,
+    0b01000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000010,
+    0b00100000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000100,
+    0b00010000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000001000,
+    0b00001000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000010000,
+    0b00000100000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000100000,
+    0b00000010000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000001000000,
+    0b00000001000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000010000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000,
+    0b00000001000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000010000000,
+    0b00000010000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000001000000,
+    0b00000100000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000100000,
+    0b00001000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000010000,
+];
+
+pub const XO_PLANES_COLLISION: &[u8] = include_bytes!("../../../../roms/xo-planes-collision.ch8");
@@ -2,8 +2,11 @@ mod data;
 mod utils;
 
 use pretty_assertions::assert_eq;
-use crate::core;
+use crate::core::{self, HEIGHT, WIDTH};
+use crate::core::cdp1802::{Bus, Cdp1802};
 use basic_emu_frontend::Core;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 macro_rules! core_tests {
     ($($name:ident: $value:expr,)*) => {
@@ -23,31 +26,61 @@ macro_rules! core_tests {
     }
 }
 
+// Same as core_tests, but for ROMs that draw on both XO-CHIP planes
+macro_rules! xo_plane_tests {
+    ($($name:ident: $value:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                let (rom, expected_0, expected_1) = $value;
+                let mut chip8 = core::Chip8::new(core::Target::XO, 16, rom.to_vec());
+                for _i in 0 .. 60 {
+                    chip8.run_frame();
+                }
+                assert_eq!(utils::pretty_plane(&chip8.buffer_planes[0]), utils::pretty_plane(&expected_0));
+                assert_eq!(utils::pretty_plane(&chip8.buffer_planes[1]), utils::pretty_plane(&expected_1));
+            }
+        )*
+    }
+}
+
 core_tests! {
     test_1_chip8_logo_chip: (core::Target::Chip, data::LOGO, data::LOGO_PLANE, 0),
     test_1_chip8_logo_super_modern: (core::Target::SuperModern, data::LOGO, data::LOGO_PLANE, 0),
     test_1_chip8_logo_super_legacy: (core::Target::SuperLegacy, data::LOGO, data::LOGO_PLANE, 0),
     test_1_chip8_logo_xo: (core::Target::XO, data::LOGO, data::LOGO_PLANE, 0),
+    test_1_chip8_logo_chip48: (core::Target::Chip48, data::LOGO, data::LOGO_PLANE, 0),
+    test_1_chip8_logo_super10: (core::Target::Super10, data::LOGO, data::LOGO_PLANE, 0),
 
     test_2_ibm_logo_chip: (core::Target::Chip, data::IBM, data::IBM_PLANE, 0),
     test_2_ibm_logo_super_modern: (core::Target::SuperModern, data::IBM, data::IBM_PLANE, 0),
     test_2_ibm_logo_super_legacy: (core::Target::SuperLegacy, data::IBM, data::IBM_PLANE, 0),
     test_2_ibm_logo_xo: (core::Target::XO, data::IBM, data::IBM_PLANE, 0),
+    test_2_ibm_logo_chip48: (core::Target::Chip48, data::IBM, data::IBM_PLANE, 0),
+    test_2_ibm_logo_super10: (core::Target::Super10, data::IBM, data::IBM_PLANE, 0),
+    test_2_ibm_logo_mega_chip: (core::Target::MegaChip, data::IBM, data::IBM_PLANE, 0),
 
     test_3_corax_chip: (core::Target::Chip, data::CORAX, data::CORAX_PLANE, 0),
     test_3_corax_super_modern: (core::Target::SuperModern, data::CORAX, data::CORAX_PLANE, 0),
     test_3_corax_super_legacy: (core::Target::SuperLegacy, data::CORAX, data::CORAX_PLANE, 0),
     test_3_corax_xo: (core::Target::XO, data::CORAX, data::CORAX_PLANE, 0),
+    test_3_corax_chip48: (core::Target::Chip48, data::CORAX, data::CORAX_PLANE, 0),
+    test_3_corax_super10: (core::Target::Super10, data::CORAX, data::CORAX_PLANE, 0),
 
     test_4_flags_chip: (core::Target::Chip, data::FLAGS, data::FLAGS_PLANE, 0),
     test_4_flags_super_modern: (core::Target::SuperModern, data::FLAGS, data::FLAGS_PLANE, 0),
     test_4_flags_super_legacy: (core::Target::SuperLegacy, data::FLAGS, data::FLAGS_PLANE, 0),
     test_4_flags_xo: (core::Target::XO, data::FLAGS, data::FLAGS_PLANE, 0),
+    test_4_flags_chip48: (core::Target::Chip48, data::FLAGS, data::FLAGS_PLANE, 0),
+    test_4_flags_super10: (core::Target::Super10, data::FLAGS, data::FLAGS_PLANE, 0),
+    test_4_flags_mega_chip: (core::Target::MegaChip, data::FLAGS, data::FLAGS_PLANE, 0),
 
     test_5_quirks_chip: (core::Target::Chip, data::QUIRKS, data::QUIRKS_CHIP_PLANE, 1),
     test_5_quirks_super_modern: (core::Target::SuperModern, data::QUIRKS, data::QUIRKS_SUPER_MODERN_PLANE, 2),
     test_5_quirks_super_legacy: (core::Target::SuperLegacy, data::QUIRKS, data::QUIRKS_SUPER_LEGACY_PLANE, 4),
     test_5_quirks_xo: (core::Target::XO, data::QUIRKS, data::QUIRKS_XO_PLANE, 3),
+    test_5_quirks_chip48: (core::Target::Chip48, data::QUIRKS, data::QUIRKS_CHIP48_PLANE, 1),
+    test_5_quirks_super10: (core::Target::Super10, data::QUIRKS, data::QUIRKS_SUPER10_PLANE, 4),
 
     test_8_scrolling_super_modern_low: (core::Target::SuperModern, data::SCROLLING, data::SCROLLING_SUPER_MODERN_LOW_PLANE, 1),
     test_8_scrolling_super_modern_high: (core::Target::SuperModern, data::SCROLLING, data::SCROLLING_SUPER_HIGH_PLANE, 3),
@@ -56,3 +89,928 @@ core_tests! {
     test_8_scrolling_xo_low: (core::Target::XO, data::SCROLLING, data::SCROLLING_XO_LOW_PLANE, 4),
     test_8_scrolling_xo_high: (core::Target::XO, data::SCROLLING, data::SCROLLING_XO_HIGH_PLANE, 5),
 }
+
+// CHIP-48 and SUPER-CHIP 1.0 don't have the scroll opcodes, so the scrolling test stops at the first one it uses
+#[test]
+#[should_panic(expected = "Unimplemented opcode 0xfc")]
+fn test_8_scrolling_chip48() {
+    let mut chip8 = core::Chip8::new(core::Target::Chip48, 16, data::SCROLLING.to_vec());
+    chip8.mem[0x1FF] = 1;
+    for _i in 0 .. 600 {
+        chip8.run_frame();
+    }
+}
+
+#[test]
+#[should_panic(expected = "Unimplemented opcode 0xfc")]
+fn test_8_scrolling_super10() {
+    let mut chip8 = core::Chip8::new(core::Target::Super10, 16, data::SCROLLING.to_vec());
+    chip8.mem[0x1FF] = 2;
+    for _i in 0 .. 600 {
+        chip8.run_frame();
+    }
+}
+
+// An 8x4 sprite at the top left, and a 16x16 sprite wrapping around the bottom right, with different data for each plane
+xo_plane_tests! {
+    test_xo_planes_low: (data::XO_PLANES, data::XO_PLANES_LOW_PLANE_0, data::XO_PLANES_LOW_PLANE_1),
+    test_xo_planes_high: (data::XO_PLANES_HIRES, data::XO_PLANES_HIGH_PLANE_0, data::XO_PLANES_HIGH_PLANE_1),
+}
+
+#[test]
+fn test_xo_planes_collision() {
+    // The sprite is drawn twice on both planes, but only has pixels on plane 0.
+    // The collision on plane 0 should still set VF, which is copied to V5.
+    let mut chip8 = core::Chip8::new(core::Target::XO, 16, data::XO_PLANES_COLLISION.to_vec());
+    chip8.run_frame();
+    assert_eq!(chip8.r_v[5], 1);
+}
+
+#[test]
+fn test_save_state_round_trip() {
+    let mut chip8 = core::Chip8::new(core::Target::XO, 16, data::CORAX.to_vec());
+    for _i in 0 .. 5 {
+        chip8.run_frame();
+    }
+    let state = chip8.save_state();
+    for _i in 0 .. 600 {
+        chip8.run_frame();
+    }
+    let expected = chip8.buffer_planes;
+
+    let mut loaded = core::Chip8::new(core::Target::XO, 16, data::CORAX.to_vec());
+    assert!(loaded.load_state(&state));
+    assert_eq!(loaded.save_state(), state);
+    for _i in 0 .. 600 {
+        loaded.run_frame();
+    }
+    assert_eq!(utils::pretty_plane(&loaded.buffer_planes[0]), utils::pretty_plane(&expected[0]));
+    assert!(!loaded.load_state(&state[1 ..]));
+}
+
+#[test]
+fn test_save_state_keeps_configuration() {
+    let mut chip8 = core::Chip8::new(core::Target::SuperLegacy, 16, data::CORAX.to_vec());
+    let mut quirks = core::Quirks::for_target(&core::Target::SuperLegacy);
+    quirks.wrap = true;
+    quirks.collision_rows = false;
+    quirks.pal_timing = true;
+    chip8.set_quirks(quirks);
+    chip8.set_persistence(core::Persistence::Phosphor);
+    chip8.set_blend_frames(5);
+    chip8.set_clock_hz(1000.0);
+    for _i in 0 .. 5 {
+        chip8.run_frame();
+    }
+    let state = chip8.save_state();
+    let _clock_carry = chip8.clock_carry;
+
+    // The state was saved with the same settings, but loading it mustn't reset them either
+    let mut loaded = core::Chip8::new(core::Target::SuperLegacy, 16, data::CORAX.to_vec());
+    loaded.set_quirks(quirks);
+    loaded.set_persistence(core::Persistence::Phosphor);
+    loaded.set_blend_frames(5);
+    loaded.set_clock_hz(1000.0);
+    loaded.clock_carry = _clock_carry;
+    assert!(loaded.load_state(&state));
+    assert_eq!(loaded.quirks, quirks);
+    assert_eq!(loaded.frame_rate, 50.0);
+    assert_eq!(loaded.clock_hz, 1000.0);
+    assert_eq!(loaded.clock_carry, _clock_carry);
+    assert_eq!(loaded.persistence.mode, core::Persistence::Phosphor);
+    assert_eq!(loaded.persistence.blend_frames, 5);
+
+    // Pointers out of memory are rejected, and the machine is left as it was
+    let mut bad_i = state.clone();
+    bad_i[25 .. 29].copy_from_slice(&0x10000_u32.to_le_bytes());
+    chip8.run_frame();
+    let _before = chip8.save_state();
+    assert!(!chip8.load_state(&bad_i));
+    assert_eq!(chip8.save_state(), _before);
+    assert_eq!(chip8.quirks, quirks);
+}
+
+#[test]
+fn test_persistence_phosphor() {
+    // Draw a single pixel at (0, 0) one frame, then clear it the next frame
+    let rom = vec![0xA2, 0x08, 0xD0, 0x01, 0x00, 0xE0, 0x12, 0x06, 0x80];
+    let mut chip8 = core::Chip8::new(core::Target::XO, 1, rom);
+    chip8.set_persistence(core::Persistence::Phosphor);
+    let palette = core::Palette::Octo.colors();
+    let mut frame = vec![0; chip8.get_width() * chip8.get_height() * 4];
+    for _i in 0 .. 2 {
+        chip8.run_frame();
+    }
+    chip8.draw(&mut frame);
+    assert_eq!(frame[0 .. 4], palette[1]);
+
+    // The pixel fades out instead of disappearing immediately
+    chip8.run_frame();
+    chip8.draw(&mut frame);
+    assert!(frame[0] < palette[1][0] && frame[0] > palette[0][0]);
+    for _i in 0 .. 30 {
+        chip8.run_frame();
+    }
+    chip8.draw(&mut frame);
+    assert_eq!(frame[0 .. 4], palette[0]);
+}
+
+#[test]
+fn test_persistence_blend() {
+    // Draw a single pixel at (0, 0) one frame, then clear it the next frame
+    let rom = vec![0xA2, 0x08, 0xD0, 0x01, 0x00, 0xE0, 0x12, 0x06, 0x80];
+    let mut chip8 = core::Chip8::new(core::Target::XO, 1, rom);
+    chip8.set_persistence(core::Persistence::Blend);
+    chip8.set_blend_frames(2);
+    let palette = core::Palette::Octo.colors();
+    let mut frame = vec![0; chip8.get_width() * chip8.get_height() * 4];
+    for _i in 0 .. 3 {
+        chip8.run_frame();
+    }
+
+    // The frame with the pixel and the cleared frame are averaged
+    chip8.draw(&mut frame);
+    for c in 0 .. 3 {
+        assert_eq!(frame[c], ((palette[0][c] as u32 + palette[1][c] as u32) / 2) as u8);
+    }
+    chip8.run_frame();
+    chip8.draw(&mut frame);
+    assert_eq!(frame[0 .. 4], palette[0]);
+}
+
+#[test]
+fn test_persistence_draw_on_set() {
+    // Draw a single pixel at (0, 0) one frame, erase it by drawing it again the next frame, then do nothing
+    let rom = vec![0xA2, 0x0A, 0xD0, 0x01, 0xD0, 0x01, 0x12, 0x06, 0x00, 0x00, 0x80];
+    let mut chip8 = core::Chip8::new(core::Target::XO, 1, rom);
+    chip8.set_persistence(core::Persistence::DrawOnSet);
+    let palette = core::Palette::Octo.colors();
+    let mut frame = vec![0; chip8.get_width() * chip8.get_height() * 4];
+    for _i in 0 .. 2 {
+        chip8.run_frame();
+    }
+    chip8.draw(&mut frame);
+    assert_eq!(frame[0 .. 4], palette[1]);
+
+    // The erase collides, so the screen keeps the pixel until a frame passes without drawing
+    chip8.run_frame();
+    chip8.draw(&mut frame);
+    assert_eq!(frame[0 .. 4], palette[1]);
+    chip8.run_frame();
+    chip8.draw(&mut frame);
+    assert_eq!(frame[0 .. 4], palette[0]);
+}
+
+// In high-res mode, draw a 5 row sprite twice in the same spot (5 rows collide) and then 2 rows from the bottom (3 rows clipped).
+// VF is stored in V2 and V3 after each.
+const COLLISION_ROWS_ROM: [u8; 27] = [
+    0x00, 0xFF, 0xA2, 0x16, 0x60, 0x00, 0x61, 0x00, 0xD0, 0x15, 0xD0, 0x15, 0x82, 0xF0, 0x61, 0x3E,
+    0xD0, 0x15, 0x83, 0xF0, 0x12, 0x14, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF
+];
+
+#[test]
+fn test_collision_rows_super_legacy() {
+    let mut chip8 = core::Chip8::new(core::Target::SuperLegacy, 16, COLLISION_ROWS_ROM.to_vec());
+    chip8.run_frame();
+    assert_eq!((chip8.r_v[2], chip8.r_v[3]), (5, 3));
+}
+
+#[test]
+fn test_collision_rows_clipped() {
+    // Rows clipped at the bottom of the screen set VF with the collision rows quirk, but they aren't a collision,
+    // so DrawOnSet still shows the sprite
+    let rom = vec![0x00, 0xFF, 0xA2, 0x0C, 0x60, 0x00, 0x61, 0x3E, 0xD0, 0x15, 0x12, 0x0A, 0x80, 0x80, 0x80, 0x80, 0x80];
+    let mut chip8 = core::Chip8::new(core::Target::SuperLegacy, 16, rom);
+    chip8.set_persistence(core::Persistence::DrawOnSet);
+    chip8.run_frame();
+    assert_eq!(chip8.r_v[0xF], 3);
+    let mut frame = vec![0; chip8.get_width() * chip8.get_height() * 4];
+    chip8.draw(&mut frame);
+    let _i = 62 * chip8.get_width() * 4;
+    assert_eq!(frame[_i .. _i + 4], core::Palette::Octo.colors()[1]);
+}
+
+#[test]
+fn test_collision_rows_disabled() {
+    let mut chip8 = core::Chip8::new(core::Target::SuperLegacy, 16, COLLISION_ROWS_ROM.to_vec());
+    let mut quirks = core::Quirks::for_target(&core::Target::SuperLegacy);
+    quirks.collision_rows = false;
+    chip8.set_quirks(quirks);
+    chip8.run_frame();
+    assert_eq!((chip8.r_v[2], chip8.r_v[3]), (1, 0));
+
+    let mut chip8 = core::Chip8::new(core::Target::SuperModern, 16, COLLISION_ROWS_ROM.to_vec());
+    chip8.run_frame();
+    assert_eq!((chip8.r_v[2], chip8.r_v[3]), (1, 0));
+}
+
+#[test]
+fn test_xo_four_planes() {
+    // Select all planes with FF01 and draw one pixel at (0, 0), with the pixel set in the data for every plane
+    let rom = vec![0xFF, 0x01, 0xA2, 0x0A, 0x60, 0x00, 0xD0, 0x01, 0x12, 0x08, 0x80, 0x80, 0x80, 0x80];
+    let palette = core::Palette::Octo.colors();
+    let mut frame = vec![0; WIDTH * HEIGHT * 4];
+
+    let mut chip8 = core::Chip8::new(core::Target::XO, 16, rom.clone());
+    let mut quirks = core::Quirks::for_target(&core::Target::XO);
+    quirks.four_planes = true;
+    chip8.set_quirks(quirks);
+    chip8.run_frame();
+    for p in 0 .. 4 {
+        assert_eq!(chip8.buffer_planes[p][0], 1 << 127);
+    }
+    chip8.draw(&mut frame);
+    assert_eq!(frame[0 .. 4], palette[15]);
+
+    // Without the extension, only the first two planes can be selected
+    let mut chip8 = core::Chip8::new(core::Target::XO, 16, rom);
+    chip8.run_frame();
+    assert_eq!(chip8.buffer_planes[2][0], 0);
+    assert_eq!(chip8.buffer_planes[3][0], 0);
+    chip8.draw(&mut frame);
+    assert_eq!(frame[0 .. 4], palette[3]);
+}
+
+#[test]
+fn test_mega_chip_sprites() {
+    // Load a 2 colour palette from above 0xFFFF with a 24-bit I, then draw a 2x1 sprite twice,
+    // overlapping the pixel with the collision colour the second time. VF is stored in V2 and V3.
+    let mut rom = vec![
+        0x00, 0x11, 0x01, 0x01, 0x00, 0x00, 0x02, 0x02, 0x03, 0x02, 0x04, 0x01, 0x09, 0x02, 0xA2, 0x30,
+        0x60, 0x0A, 0x61, 0x05, 0xD0, 0x10, 0x82, 0xF0, 0x70, 0x01, 0xD0, 0x10, 0x83, 0xF0, 0x00, 0xE0,
+        0x12, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x01, 0x02
+    ];
+    rom.resize(0x10000 - 0x200, 0);
+    rom.extend_from_slice(&[0xFF, 0x11, 0x22, 0x33, 0xFF, 0x44, 0x55, 0x66]);
+    let mut chip8 = core::Chip8::new(core::Target::MegaChip, 32, rom);
+    chip8.run_frame();
+    assert_eq!((chip8.r_v[2], chip8.r_v[3]), (0, 1));
+
+    let (width, height) = (chip8.get_width(), chip8.get_height());
+    assert_eq!((width, height), (256, 192));
+    let mut frame = vec![0; width * height * 4];
+    chip8.draw(&mut frame);
+    let pixel = |x: usize, y: usize| frame[(y * width + x) * 4 .. (y * width + x) * 4 + 4].to_vec();
+    assert_eq!(pixel(10, 5), [0x11, 0x22, 0x33, 0xFF]);
+    assert_eq!(pixel(11, 5), [0x11, 0x22, 0x33, 0xFF]);
+    assert_eq!(pixel(12, 5), [0x44, 0x55, 0x66, 0xFF]);
+    assert_eq!(pixel(13, 5), [0x00, 0x00, 0x00, 0xFF]);
+}
+
+#[test]
+fn test_mega_chip_long_i_and_palette() {
+    // 01nn nnnn sets a 24-bit I and 02nn loads the palette from I, the Mega8 order rather than the other way around
+    let rom = vec![0x01, 0x12, 0x34, 0x56, 0x12, 0x04];
+    let mut chip8 = core::Chip8::new(core::Target::MegaChip, 2, rom);
+    chip8.run_frame();
+    assert_eq!(chip8.r_i, 0x123456);
+
+    let rom = vec![0xA2, 0x06, 0x02, 0x01, 0x12, 0x04, 0xFF, 0xAA, 0xBB, 0xCC];
+    let mut chip8 = core::Chip8::new(core::Target::MegaChip, 3, rom);
+    chip8.run_frame();
+    assert_eq!(chip8.mega.palette[1], [0xAA, 0xBB, 0xCC, 0xFF]);
+}
+
+#[test]
+fn test_oversized_rom() {
+    // ROMs that don't fit in memory are cut off at the end of it instead of panicking
+    let rom: Vec<u8> = (0 .. 0x10100).map(|i| (i % 251) as u8).collect();
+    let mut chip8 = core::Chip8::new(core::Target::XO, 10, rom.clone());
+    assert_eq!(chip8.mem.len(), 0x10000);
+    assert_eq!(chip8.mem[0x200 ..], rom[.. 0xFE00]);
+    chip8.reset();
+    assert_eq!(chip8.mem[0x200 ..], rom[.. 0xFE00]);
+}
+
+#[test]
+fn test_mega_chip_memory_edges() {
+    // Draw a 1x1 sprite on the cleared screen and store VF in V2, then draw a 2x1 sprite and play a sound
+    // from the last byte of memory
+    let rom = vec![
+        0x00, 0x11, 0x03, 0x01, 0x04, 0x01, 0xA2, 0x20, 0x60, 0x00, 0xD0, 0x01, 0x82, 0xF0, 0x01, 0xFF,
+        0xFF, 0xFF, 0x03, 0x02, 0xD0, 0x01, 0x06, 0x00, 0x12, 0x18, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x01
+    ];
+    let mut chip8 = core::Chip8::new(core::Target::MegaChip, 32, rom);
+    chip8.run_frame();
+
+    // The background isn't the default collision colour
+    assert_eq!(chip8.r_v[2], 0);
+    // The sprite wraps around to the start of memory and the sound has no room for any samples
+    assert_eq!(chip8.mega.active_indices[1], chip8.mem[0]);
+    assert_eq!(chip8.mega.sound.map(|sound| sound.length), Some(0));
+}
+
+#[test]
+fn test_chip8x_colors() {
+    // Switch to a black background, colour a block of zones green with Bxy0 and two rows white with Bxyn,
+    // then draw a pixel in each. 5xy1 is stored in V3 and V9 is only set if key 0 on the second keypad is up.
+    let rom = vec![
+        0x02, 0xA0, 0x60, 0x11, 0x61, 0x00, 0x62, 0x04, 0xB0, 0x20, 0x63, 0x05, 0x64, 0x33, 0x53, 0x41,
+        0x65, 0x20, 0x66, 0x02, 0x67, 0x07, 0xB5, 0x72, 0xA3, 0x2C, 0x60, 0x08, 0xD0, 0x11, 0x60, 0x20,
+        0x61, 0x02, 0xD0, 0x11, 0x68, 0x00, 0xE8, 0xF2, 0x69, 0x01, 0x13, 0x2A, 0x80
+    ];
+    let mut chip8 = core::Chip8::new(core::Target::Chip8X, 16, rom);
+    chip8.press_key(0x10);
+    for _i in 0 .. 3 {
+        chip8.run_frame();
+    }
+    assert_eq!((chip8.r_v[3], chip8.r_v[9]), (0x30, 0));
+
+    let mut frame = vec![0; WIDTH * HEIGHT * 4];
+    chip8.draw(&mut frame);
+    let pixel = |x: usize, y: usize| frame[(y * WIDTH + x) * 4 .. (y * WIDTH + x) * 4 + 4].to_vec();
+    assert_eq!(pixel(16, 0), [0x00, 0xFF, 0x00, 0xFF]);
+    assert_eq!(pixel(64, 4), [0xFF, 0xFF, 0xFF, 0xFF]);
+    assert_eq!(pixel(0, 0), [0x00, 0x00, 0x00, 0xFF]);
+}
+
+#[test]
+fn test_hires_chip_two_page() {
+    // 1260 or 1244 at the start of the ROM switches to the 64x64 display and runs from 0x2C0, which clears the screen
+    // with 0230 and draws a pixel at (1, 40), below the bottom of the normal low-res display.
+    for _entry in [0x60, 0x44] {
+        let mut rom = vec![0x12, _entry];
+        rom.resize(0xC0, 0);
+        rom.extend_from_slice(&[0x02, 0x30, 0xA2, 0xCC, 0x60, 0x01, 0x61, 0x28, 0xD0, 0x11, 0x12, 0xCA, 0x80]);
+        let mut chip8 = core::Chip8::new(core::Target::HiresChip, 16, rom);
+        for _i in 0 .. 2 {
+            chip8.run_frame();
+        }
+        assert_eq!(chip8.buffer_planes[0][40], 1 << 126);
+
+        // Both axes are doubled, so the pixel is a 2x2 square in the 128x128 frame
+        let palette = core::Palette::Octo.colors();
+        let (width, height) = (chip8.get_width(), chip8.get_height());
+        assert_eq!((width, height), (WIDTH, HEIGHT * 2));
+        let mut frame = vec![0; width * height * 4];
+        chip8.draw(&mut frame);
+        let pixel = |x: usize, y: usize| frame[(y * width + x) * 4 .. (y * width + x) * 4 + 4].to_vec();
+        for (x, y) in [(2, 80), (3, 80), (2, 81), (3, 81)] {
+            assert_eq!(pixel(x, y), palette[1]);
+        }
+        for (x, y) in [(1, 80), (4, 80), (2, 79), (2, 82)] {
+            assert_eq!(pixel(x, y), palette[0]);
+        }
+    }
+
+    // Without the jump the program runs as normal CHIP-8, with each low-res pixel covering 2x4 of the frame
+    let rom = vec![0xA2, 0x08, 0x60, 0x00, 0xD0, 0x01, 0x12, 0x06, 0x80];
+    let mut chip8 = core::Chip8::new(core::Target::HiresChip, 16, rom);
+    chip8.run_frame();
+    let palette = core::Palette::Octo.colors();
+    let mut frame = vec![0; chip8.get_width() * chip8.get_height() * 4];
+    chip8.draw(&mut frame);
+    let pixel = |x: usize, y: usize| frame[(y * WIDTH + x) * 4 .. (y * WIDTH + x) * 4 + 4].to_vec();
+    assert_eq!(pixel(1, 3), palette[1]);
+    assert_eq!(pixel(2, 0), palette[0]);
+    assert_eq!(pixel(0, 4), palette[0]);
+}
+
+#[test]
+fn test_eti660_display() {
+    // Draw a pixel at (0, 40), which is only on screen with the 64x48 display
+    let rom = vec![0x60, 0x00, 0x61, 0x28, 0xA6, 0x0A, 0xD0, 0x11, 0x16, 0x08, 0x80];
+    let mut chip8 = core::Chip8::new(core::Target::Eti660, 16, rom);
+    for _i in 0 .. 2 {
+        chip8.run_frame();
+    }
+    assert_eq!(chip8.buffer_planes[0][40], 1 << 127);
+    assert_eq!(chip8.get_height(), 96);
+    assert_eq!(chip8.get_frame_rate(), 50.0);
+
+    let palette = core::Palette::Octo.colors();
+    let mut frame = vec![0; WIDTH * 96 * 4];
+    chip8.draw(&mut frame);
+    let pixel = |x: usize, y: usize| frame[(y * WIDTH + x) * 4 .. (y * WIDTH + x) * 4 + 4].to_vec();
+    assert_eq!(pixel(0, 81), palette[1]);
+    assert_eq!(pixel(0, 82), palette[0]);
+}
+
+#[test]
+fn test_pal_timing() {
+    // The ETI-660 runs at 50 Hz unless the quirk is turned off, and other targets can be switched to it
+    let mut chip8 = core::Chip8::new(core::Target::Eti660, 16, vec![0x16, 0x00]);
+    chip8.set_clock_hz(600.0);
+    assert_eq!(chip8.get_frame_rate(), 50.0);
+    let mut quirks = core::Quirks::for_target(&core::Target::Eti660);
+    quirks.pal_timing = false;
+    chip8.set_quirks(quirks);
+    assert_eq!(chip8.get_frame_rate(), 60.0);
+    assert_eq!(chip8.clock, 10.0);
+
+    let mut chip8 = core::Chip8::new(core::Target::XO, 16, vec![0x12, 0x00]);
+    let mut quirks = core::Quirks::for_target(&core::Target::XO);
+    quirks.pal_timing = true;
+    chip8.set_quirks(quirks);
+    assert_eq!(chip8.get_frame_rate(), 50.0);
+}
+
+#[test]
+fn test_dream6800_keypad() {
+    // Wait for a key, then point I at its character in the font
+    let rom = vec![0xF0, 0x0A, 0xF0, 0x29, 0x12, 0x04];
+    let mut chip8 = core::Chip8::new(core::Target::Dream6800, 16, rom);
+    chip8.run_frame();
+    assert!(chip8.halting);
+
+    // CHIPOS doesn't wait for the key to be released
+    chip8.press_key(5);
+    chip8.run_frame();
+    assert_eq!(chip8.r_v[0], 5);
+    assert_eq!(chip8.r_i, 0xC000 + 5 * 5);
+    assert_eq!(chip8.mem[chip8.r_i], 0xE0);
+}
+
+#[test]
+fn test_fonts() {
+    // Point I at the small and big characters for 4
+    let rom = vec![0x60, 0x04, 0xF0, 0x29, 0xF0, 0x30, 0x12, 0x06];
+    let mut chip8 = core::Chip8::new(core::Target::SuperLegacy, 16, rom);
+    chip8.run_frame();
+    assert_eq!(chip8.mem[20 .. 25], core::FontSet::Schip11.bytes()[20 .. 25]);
+    assert_eq!(chip8.mem[chip8.r_i], 0x06);
+
+    // The font is still there after a reset
+    chip8.reset();
+    chip8.run_frame();
+    assert_eq!(chip8.mem[chip8.r_i], 0x06);
+
+    // A custom font with only the small characters keeps the big font
+    chip8.set_font(&[0xAA; 80]);
+    chip8.reset();
+    chip8.run_frame();
+    assert_eq!(chip8.mem[20], 0xAA);
+    assert_eq!(chip8.mem[chip8.r_i], 0x06);
+
+    chip8.set_font(&core::FontSet::Octo.bytes());
+    assert_eq!(chip8.mem[chip8.r_i], 0xC3);
+
+    // Changing the target keeps a custom font
+    chip8.set_font(&[0xAA; 80]);
+    chip8.set_target(core::Target::Chip);
+    chip8.reset();
+    assert_eq!(chip8.mem[20], 0xAA);
+    let state = chip8.save_state();
+    assert!(chip8.load_state(&state));
+    chip8.set_target(core::Target::XO);
+    chip8.reset();
+    assert_eq!(chip8.mem[20], 0xAA);
+
+    // Until the target's own font is set again
+    chip8.set_default_font();
+    assert_eq!(chip8.mem[.. 80], core::FontSet::Octo.bytes()[.. 80]);
+    chip8.set_target(core::Target::Chip);
+    chip8.reset();
+    assert_eq!(chip8.mem[.. 80], core::FontSet::Vip.bytes()[.. 80]);
+
+    // The COSMAC VIP's interpreter isn't overwritten by a font, whichever is set first
+    let mut chip8 = core::Chip8::new(core::Target::CosmacVip, 0, vec![0x12, 0x00]);
+    chip8.set_vip_interpreter(vec![0x55; 0x200]);
+    chip8.set_font(&core::FontSet::Octo.bytes());
+    assert!(chip8.mem[.. 0x200].iter().all(|byte| *byte == 0x55));
+    chip8.reset();
+    assert!(chip8.mem[.. 0x200].iter().all(|byte| *byte == 0x55));
+}
+
+#[test]
+fn test_memory_increment() {
+    // Store V0-V2 with I at 0x300, then check how far I was moved for each target
+    let rom = vec![0xA3, 0x00, 0xF2, 0x55, 0x12, 0x04];
+    for (target, expected) in [
+        (core::Target::Chip, 0x303),
+        (core::Target::Chip48, 0x302),
+        (core::Target::Super10, 0x302),
+        (core::Target::SuperLegacy, 0x300),
+        (core::Target::SuperModern, 0x300),
+        (core::Target::XO, 0x303)
+    ] {
+        let mut chip8 = core::Chip8::new(target.clone(), 16, rom.clone());
+        chip8.run_frame();
+        assert_eq!(chip8.r_i, expected, "{:?}", target);
+    }
+}
+
+#[test]
+fn test_clock_hz() {
+    // 90 Hz is 1.5 instructions per frame, so frames alternate between 1 and 2 instructions
+    let mut chip8 = core::Chip8::new(core::Target::Chip, 11, vec![0x70, 0x01, 0x12, 0x00]);
+    chip8.set_clock_hz(90.0);
+    chip8.reset();
+    for _i in 0 .. 4 {
+        chip8.run_frame();
+    }
+    assert_eq!(chip8.r_pc, 0x200);
+    assert_eq!(chip8.r_v[0], 3);
+
+    // Changing the clock doesn't restart the current frame, which only had one instruction left
+    chip8.set_clock(100);
+    chip8.run_inst();
+    assert_eq!(chip8.r_v[0], 4);
+    chip8.run_frame();
+    assert_eq!(chip8.r_v[0], 54);
+}
+
+#[test]
+fn test_timers_emulated_time() {
+    // Set the delay timer to 5, then spin. The timer goes by instructions run, not by calls to run_frame.
+    let mut chip8 = core::Chip8::new(core::Target::Chip, 10, vec![0x60, 0x05, 0xF0, 0x15, 0x12, 0x04]);
+    for _i in 0 .. 30 {
+        chip8.run_inst();
+    }
+    assert_eq!(chip8.r_delay, 2);
+
+    // Changing the clock partway through keeps the timer going at one tick per frame's worth of instructions
+    chip8.set_clock(20);
+    for _i in 0 .. 20 {
+        chip8.run_inst();
+    }
+    assert_eq!(chip8.r_delay, 1);
+}
+
+#[test]
+fn test_host_pacing() {
+    // Each frame adds 1 to V0, so V0 counts the frames run_frame ran for the time the clock moved on
+    let mut chip8 = core::Chip8::new(core::Target::Chip, 2, vec![0x70, 0x01, 0x12, 0x00]);
+    let now = Arc::new(Mutex::new(Duration::ZERO));
+    let _clock = now.clone();
+    chip8.set_host_pacing(true);
+    chip8.set_host_clock(Box::new(move || *_clock.lock().unwrap()));
+    let mut run_after = |ms: f32| {
+        *now.lock().unwrap() += Duration::from_secs_f32(ms / 1000.0);
+        let _before = chip8.r_v[0];
+        chip8.run_frame();
+        chip8.r_v[0] - _before
+    };
+
+    // The first call always runs one frame
+    assert_eq!(run_after(0.0), 1);
+
+    // A 60 Hz host with jittery timing runs exactly one frame each call
+    for _i in 0 .. 10 {
+        assert_eq!(run_after(12.0), 1);
+        assert_eq!(run_after(21.3), 1);
+    }
+
+    // A 144 Hz host runs 60 frames a second, at most one per call
+    let _frames: Vec<u8> = (0 .. 144).map(|_i| run_after(1000.0 / 144.0)).collect();
+    assert!(_frames.iter().all(|frames| *frames <= 1));
+    assert!((59 ..= 61).contains(&_frames.iter().sum::<u8>()));
+
+    // A stall only catches up a few frames
+    assert_eq!(run_after(1000.0), 4);
+}
+
+#[test]
+fn test_audio_spectrum() {
+    // Play an XO-CHIP square wave (8 bits off, 8 bits on) at pitch 200, which has harmonics well above Nyquist
+    let mut rom = vec![0xA2, 0x10, 0xF0, 0x02, 0x60, 200, 0xF0, 0x3A, 0x60, 0xFF, 0xF0, 0x18, 0x12, 0x0C, 0x00, 0x00];
+    rom.extend_from_slice(&[0x00, 0xFF].repeat(8));
+    let mut chip8 = core::Chip8::new(core::Target::XO, 1000, rom);
+    let _sample_rate = 44100.0;
+    chip8.set_num_output_channels(1);
+    chip8.set_seconds_per_output_sample(1.0 / _sample_rate);
+    for _i in 0 .. 10 {
+        chip8.run_frame();
+    }
+    let _count = 4096;
+    let samples: Vec<f32> = (0 .. 1000 + _count).map(|_i| chip8.get_sample()).skip(1000).collect();
+
+    // Hann windowed power spectrum
+    let _spectrum: Vec<f32> = (0 .. _count / 2).map(|k| {
+        let (mut re, mut im) = (0.0_f64, 0.0_f64);
+        for (n, sample) in samples.iter().enumerate() {
+            let _window = 0.5 - 0.5 * (2.0 * std::f64::consts::PI * n as f64 / _count as f64).cos();
+            let _angle = 2.0 * std::f64::consts::PI * (k * n) as f64 / _count as f64;
+            re += *sample as f64 * _window * _angle.cos();
+            im -= *sample as f64 * _window * _angle.sin();
+        }
+        (re * re + im * im) as f32
+    }).collect();
+
+    // The fundamental is the strongest component, and almost everything else is at its harmonics (no aliases)
+    let _fundamental = 4000.0 * 2_f32.powf((200.0 - 64.0) / 48.0) / 16.0;
+    let _bin_width = _sample_rate / _count as f32;
+    let _peak = (1 .. _spectrum.len()).max_by(|a, b| _spectrum[*a].total_cmp(&_spectrum[*b])).unwrap();
+    assert!((_peak as f32 * _bin_width - _fundamental).abs() < 2.0 * _bin_width);
+    let (mut harmonic, mut other) = (0.0, 0.0);
+    for (k, power) in _spectrum.iter().enumerate().skip(3) {
+        let _harmonic_number = (k as f32 * _bin_width / _fundamental).round();
+        if _harmonic_number >= 1.0 && (k as f32 * _bin_width - _harmonic_number * _fundamental).abs() < 4.0 * _bin_width {
+            harmonic += power;
+        } else {
+            other += power;
+        }
+    }
+    assert!(other / harmonic < 0.001, "{}", other / harmonic);
+}
+
+#[test]
+fn test_xo_audio_pattern() {
+    // Load a pattern with F002 and play it at 32 output samples per bit. The pattern plays from the most significant bit.
+    let _pattern = [0x80, 0x01, 0xC3, 0x5A, 0xF0, 0x0F, 0xAA, 0x55, 0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE, 0xFF];
+    let mut rom = vec![0xA2, 0x06, 0xF0, 0x02, 0x12, 0x04];
+    rom.extend_from_slice(&_pattern);
+    let mut chip8 = core::Chip8::new(core::Target::XO, 1000, rom);
+    chip8.set_num_output_channels(1);
+    chip8.set_seconds_per_output_sample(1.0 / (4000.0 * 32.0));
+    chip8.run_inst();
+    chip8.run_inst();
+    chip8.r_audio = 255;
+    chip8.audio_oscillator = 0.0;
+    while chip8.get_sample_queue_length() > 0 {
+        chip8.get_sample();
+    }
+    for _i in 0 .. 32 * 130 {
+        chip8.run_audio(1.0 / (4000.0 * 32.0));
+    }
+    let samples: Vec<f32> = (0 .. 32 * 130).map(|_i| chip8.get_sample()).collect();
+
+    // Check the middle of every bit, allowing for the delay of the decimation filter
+    for (i, byte) in _pattern.iter().enumerate() {
+        for bit in 0 .. 8 {
+            let _expected = if byte & (0x80 >> bit) != 0 { 1.0 } else { -1.0 };
+            let _sample = samples[(i * 8 + bit) * 32 + 16 + 12];
+            assert!(_sample * _expected > 0.07, "byte {} bit {}: {}", i, bit, _sample);
+        }
+    }
+}
+
+#[test]
+fn test_audio_phase_continuity() {
+    // Sound the buzzer for 2 frames, then stay silent. The oscillator keeps running instead of restarting.
+    let mut chip8 = core::Chip8::new(core::Target::XO, 10, vec![0x60, 0x02, 0xF0, 0x18, 0x12, 0x04]);
+    chip8.set_num_output_channels(1);
+    chip8.set_seconds_per_output_sample(1.0 / 48000.0);
+    for _i in 0 .. 5 {
+        chip8.run_frame();
+    }
+    assert_eq!(chip8.r_audio, 0);
+    let _expected = (5.0 / 60.0 * 4000.0) % 128.0;
+    assert!((chip8.audio_oscillator - _expected).abs() < 0.05, "{}", chip8.audio_oscillator);
+}
+
+#[test]
+fn test_audio_pattern_mid_sample() {
+    // Switch from an all ones pattern to an all zeros one at a sample boundary, or partway through a sample
+    let play = |switch_time: f32| {
+        let mut chip8 = core::Chip8::new(core::Target::XO, 1000, vec![0x12, 0x00]);
+        chip8.set_num_output_channels(1);
+        chip8.set_seconds_per_output_sample(1.0 / 1024.0);
+        chip8.r_audio = 255;
+        chip8.audio_buffer = u128::MAX;
+        chip8.run_audio(switch_time);
+        chip8.audio_buffer = 0;
+        chip8.run_audio(60.0 / 1024.0 - switch_time);
+        (0 .. 60).map(|_i| chip8.get_sample()).collect::<Vec<f32>>()
+    };
+    let _before = play(20.0 / 1024.0);
+    let _after = play(21.0 / 1024.0);
+    let _partway = play(20.375 / 1024.0);
+
+    // Only the part of the sample after the switch plays the new pattern, so the extra energy over switching
+    // at the start of the sample is in proportion to the part of the sample played before the switch
+    let _difference = |a: &Vec<f32>, b: &Vec<f32>| a.iter().zip(b.iter()).map(|(x, y)| x - y).sum::<f32>();
+    let _ratio = _difference(&_partway, &_before) / _difference(&_after, &_before);
+    assert!((_ratio - 0.375).abs() < 0.01, "{}", _ratio);
+}
+
+#[test]
+fn test_audio_recording() {
+    // The recording holds exactly the samples that were output, and encodes to a float WAV file
+    let mut chip8 = core::Chip8::new(core::Target::XO, 100, vec![0x60, 0x10, 0xF0, 0x18, 0x12, 0x04]);
+    chip8.set_num_output_channels(2);
+    chip8.set_seconds_per_output_sample(1.0 / 8000.0);
+    chip8.start_audio_recording();
+    let mut samples = Vec::new();
+    for _i in 0 .. 30 {
+        chip8.run_frame();
+        while chip8.get_sample_queue_length() > 0 {
+            samples.push(chip8.get_sample());
+        }
+    }
+    let mut recording = chip8.take_audio_recording();
+    chip8.run_frame();
+    while chip8.get_sample_queue_length() > 0 {
+        samples.push(chip8.get_sample());
+    }
+    recording.extend(chip8.stop_audio_recording());
+    assert_eq!(recording, samples);
+    assert!(samples.iter().any(|sample| *sample != 0.0));
+    chip8.run_frame();
+    assert!(chip8.take_audio_recording().is_empty());
+
+    let _wav = core::wav::encode_wav(&recording, chip8.get_num_output_channels(), chip8.get_sample_rate());
+    let _u16_at = |pos: usize| u16::from_le_bytes([_wav[pos], _wav[pos + 1]]);
+    let _u32_at = |pos: usize| u32::from_le_bytes(_wav[pos .. pos + 4].try_into().unwrap());
+    assert_eq!(&_wav[0 .. 4], b"RIFF");
+    assert_eq!(_u32_at(4) as usize, _wav.len() - 8);
+    assert_eq!(&_wav[8 .. 16], b"WAVEfmt ");
+    assert_eq!((_u16_at(20), _u16_at(22), _u32_at(24), _u16_at(34)), (3, 2, 8000, 32));
+    assert_eq!(&_wav[38 .. 42], b"fact");
+    assert_eq!(_u32_at(46) as usize, recording.len() / 2);
+    assert_eq!(&_wav[50 .. 54], b"data");
+    assert_eq!(_u32_at(54) as usize, recording.len() * 4);
+    let decoded: Vec<f32> = _wav[58 ..].chunks_exact(4).map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap())).collect();
+    assert_eq!(decoded, recording);
+}
+
+#[test]
+fn test_gif_recording() {
+    // Toggle a sprite after waiting 10 frames, the sprite waits for the next frame to draw so the display changes every 11 frames.
+    // The frames in between are deduplicated.
+    let rom = [0x60, 0x0A, 0xF0, 0x15, 0xF1, 0x07, 0x31, 0x00, 0x12, 0x04, 0xD0, 0x05, 0x12, 0x00];
+    let mut chip8 = core::Chip8::new(core::Target::Chip, 20, rom.to_vec());
+    chip8.start_video_recording(core::VideoFormat::Gif);
+    assert!(chip8.is_recording_video());
+    let mut gif = Vec::new();
+    for _i in 0 .. 60 {
+        chip8.run_frame();
+        gif.extend(chip8.take_video_recording());
+    }
+    gif.extend(chip8.stop_video_recording());
+    assert!(!chip8.is_recording_video());
+
+    let frames = utils::decode_gif(&gif);
+    assert_eq!(utils::decode_gif_external(&gif), frames);
+    assert_eq!(frames.len(), 6);
+    assert_eq!(frames.iter().map(|(delay, _frame)| *delay as u32).sum::<u32>(), 100);
+    assert!(frames[1 .. 5].iter().all(|(delay, _frame)| (18 ..= 19).contains(delay)));
+    let _background = core::Palette::default().colors()[0];
+    let _foreground = core::Palette::default().colors()[1];
+    let _lit = |frame: &Vec<[u8; 3]>| frame.iter().filter(|pixel| pixel[..] == _foreground[0 .. 3]).count();
+    assert!(frames[0].1.iter().all(|pixel| pixel[..] == _background[0 .. 3]));
+    assert!(_lit(&frames[1].1) > 0);
+    assert_eq!(_lit(&frames[2].1), 0);
+    assert_eq!(frames[1].1, frames[3].1);
+}
+
+#[test]
+fn test_gif_encoder() {
+    // Enough noise with 256 colours to fill the LZW table several times over
+    let mut seed = 12345_u32;
+    let pixels: Vec<[u8; 4]> = (0 .. WIDTH * HEIGHT).map(|_i| {
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        let _value = (seed >> 16) as u8;
+        [_value, _value ^ 0x55, 0, 0xFF]
+    }).collect();
+    let mut encoder = core::gif::GifEncoder::new(WIDTH, HEIGHT);
+    encoder.write_frame(WIDTH, HEIGHT, &pixels, 5);
+    encoder.write_frame(WIDTH, HEIGHT, &pixels[.. WIDTH * HEIGHT / 2].repeat(2), 7);
+    let gif = encoder.finish();
+    let frames = utils::decode_gif(&gif);
+    assert_eq!(utils::decode_gif_external(&gif), frames);
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0].0, 5);
+    assert_eq!(frames[1].0, 7);
+    assert!(frames[0].1.iter().zip(pixels.iter()).all(|(decoded, pixel)| decoded[..] == pixel[0 .. 3]));
+}
+
+#[test]
+fn test_raw_video_recording() {
+    // Raw frames are written for every frame, even when they're the same
+    let mut chip8 = core::Chip8::new(core::Target::Chip, 20, vec![0xD0, 0x05, 0x12, 0x02]);
+    chip8.start_video_recording(core::VideoFormat::Raw);
+    for _i in 0 .. 10 {
+        chip8.run_frame();
+    }
+    let raw = chip8.stop_video_recording();
+    assert_eq!(raw.len(), 10 * WIDTH * HEIGHT * 4);
+    let _frame_size = WIDTH * HEIGHT * 4;
+    assert_eq!(raw[_frame_size * 8 .. _frame_size * 9], raw[_frame_size * 9 ..]);
+    assert!(raw.chunks_exact(4).any(|pixel| pixel == core::Palette::default().colors()[1]));
+}
+
+#[test]
+fn test_vip_timing() {
+    // 7001 and 1200 take 102 cycles together, so 26 increments fit in a frame before the budget runs out
+    let mut chip8 = core::Chip8::new(core::Target::Chip, 11, vec![0x70, 0x01, 0x12, 0x00]);
+    chip8.set_timing(core::Timing::Vip);
+    chip8.run_frame();
+    assert_eq!(chip8.r_v[0], 26);
+
+    // Clearing the screen takes longer than a whole frame
+    let mut chip8 = core::Chip8::new(core::Target::Chip, 11, vec![0x00, 0xE0, 0x70, 0x01, 0x12, 0x02]);
+    chip8.set_timing(core::Timing::Vip);
+    chip8.run_frame();
+    assert_eq!(chip8.r_v[0], 0);
+    assert_eq!(chip8.r_pc, 0x202);
+}
+
+#[test]
+fn test_cdp1802_arithmetic() {
+    struct TestBus {
+        mem: Vec<u8>
+    }
+    impl Bus for TestBus {
+        fn read(&mut self, addr: u16) -> u8 { self.mem[addr as usize] }
+        fn write(&mut self, addr: u16, value: u8) { self.mem[addr as usize] = value; }
+        fn input(&mut self, _port: u8) -> u8 { 0 }
+        fn output(&mut self, _port: u8, _value: u8) {}
+        fn flag(&self, _n: u8) -> bool { false }
+    }
+    // 0x10 - 0x20 with SMI (borrow, DF = 0), then SHRC shifts the borrow flag into bit 7 and the old bit 0 into DF,
+    // then a long branch to 0x0010 since DF is clear, which stores D with STR R3 (R3 = 0x0020)
+    let mut bus = TestBus { mem: vec![0; 0x100] };
+    bus.mem[.. 15].copy_from_slice(&[0xF8, 0x20, 0xA3, 0xF8, 0x10, 0xFF, 0x20, 0x76, 0xCB, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00]);
+    bus.mem[0x10] = 0x53;
+    let mut cpu = Cdp1802::new();
+    let mut cycles = 0;
+    for _i in 0 .. 7 {
+        cycles += cpu.step(&mut bus);
+    }
+    assert_eq!(bus.mem[0x20], 0x78);
+    assert_eq!(cycles, 15);
+}
+
+#[test]
+fn test_cosmac_vip_display() {
+    // A minimal stand-in for the CHIP-8 interpreter: switch the program counter to R3 and turn on the display and Q,
+    // with an interrupt routine that points R0 at 0xC00 for each frame. Each line reads the next 8 bytes, so the first pixel
+    // of line 0 (0xC00), the last pixel of line 1 (0xC08) and all of the first byte of line 2 (0xC10) are set.
+    let interpreter = vec![
+        0xF8, 0x00, 0xB1, 0xF8, 0x20, 0xA1, 0xF8, 0x0E, 0xB2, 0xF8, 0xFF, 0xA2, 0xF8, 0x14, 0xA3, 0xD3,
+        0x00, 0x00, 0x00, 0x00, 0xE2, 0x69, 0x7B, 0x30, 0x17, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x70,
+        0xF8, 0x0C, 0xB0, 0xF8, 0x00, 0xA0, 0x22, 0x78, 0x30, 0x1F
+    ];
+    let mut rom = vec![0; 0xA11];
+    rom[0xA00] = 0x80;
+    rom[0xA0F] = 0x01;
+    rom[0xA10] = 0xFF;
+    let mut chip8 = core::Chip8::new(core::Target::CosmacVip, 0, rom);
+    chip8.set_vip_interpreter(interpreter);
+    for _i in 0 .. 2 {
+        chip8.run_frame();
+    }
+    assert_eq!(chip8.buffer_planes[0][0], 1 << 127 | 1);
+    assert_eq!(chip8.buffer_planes[0][1], 0xFF << 120);
+    assert_eq!(chip8.buffer_planes[0][2], 0);
+    assert_eq!(chip8.r_audio, 1);
+
+    // Each line is a row of the frame, with its pixels doubled horizontally
+    let palette = core::Palette::Octo.colors();
+    let (width, height) = (chip8.get_width(), chip8.get_height());
+    assert_eq!((width, height), (128, 128));
+    let mut frame = vec![0; width * height * 4];
+    chip8.draw(&mut frame);
+    let pixel = |x: usize, y: usize| frame[(y * width + x) * 4 .. (y * width + x) * 4 + 4].to_vec();
+    assert_eq!((pixel(0, 0), pixel(1, 0), pixel(2, 0)), (palette[1].to_vec(), palette[1].to_vec(), palette[0].to_vec()));
+    assert_eq!((pixel(127, 1), pixel(125, 1), pixel(0, 1)), (palette[1].to_vec(), palette[0].to_vec(), palette[0].to_vec()));
+    assert_eq!((pixel(15, 2), pixel(16, 2), pixel(0, 3)), (palette[1].to_vec(), palette[0].to_vec(), palette[0].to_vec()));
+
+    // Instructions often run past the end of the frame, but each run_frame still only runs one frame's worth of audio
+    chip8.set_num_output_channels(1);
+    chip8.set_seconds_per_output_sample(1.0 / 44100.0);
+    for _i in 0 .. 10 {
+        chip8.run_frame();
+        let _samples = chip8.get_sample_queue_length();
+        assert!((734 ..= 736).contains(&_samples));
+        while chip8.get_sample_queue_length() > 0 {
+            chip8.get_sample();
+        }
+    }
+
+    // The machine state should survive a reset
+    chip8.reset();
+    for _i in 0 .. 2 {
+        chip8.run_frame();
+    }
+    assert_eq!(chip8.buffer_planes[0][0], 1 << 127 | 1);
+}
+
+#[test]
+fn test_machine_code_subroutine() {
+    // Draw a pixel, then call a machine code routine at 0x210 that sets V3 to 0x42 through memory
+    // and clears the display page, then returns with D4
+    let mut rom = vec![0xA2, 0x40, 0xD0, 0x01, 0x02, 0x10, 0x12, 0x06];
+    rom.resize(0x10, 0);
+    rom.extend_from_slice(&[
+        0xF8, 0x0E, 0xB6, 0xF8, 0xF3, 0xA6, 0xF8, 0x42, 0x56, 0xF8, 0x0F, 0xBB, 0xF8, 0x00, 0xAB, 0xF8,
+        0x00, 0x5B, 0x1B, 0x8B, 0x3A, 0x1F, 0xD4
+    ]);
+    rom.resize(0x40, 0);
+    rom.push(0x80);
+    let mut chip8 = core::Chip8::new(core::Target::Chip, 16, rom);
+    chip8.run_frame();
+    assert_eq!(chip8.buffer_planes[0][0], 1 << 127);
+    chip8.run_frame();
+    assert_eq!(chip8.buffer_planes[0][0], 0);
+    assert_eq!(chip8.r_v[3], 0x42);
+    assert_eq!(chip8.r_pc, 0x206);
+    assert_eq!(chip8.take_machine_code_error(), None);
+}
+
+#[test]
+fn test_machine_code_errors() {
+    // Call into the interpreter at 0x123, then call a routine at 0x206 that branches to itself forever
+    let rom = vec![0x01, 0x23, 0x02, 0x06, 0x12, 0x04, 0x30, 0x06];
+    let mut chip8 = core::Chip8::new(core::Target::Chip, 16, rom);
+    chip8.run_inst();
+    assert_eq!(chip8.take_machine_code_error(), Some(core::MachineCodeError::InterpreterCall(0x123)));
+    chip8.run_inst();
+    assert_eq!(chip8.take_machine_code_error(), Some(core::MachineCodeError::NoReturn(0x206)));
+    assert_eq!(chip8.r_pc, 0x204);
+    chip8.run_inst();
+    assert_eq!(chip8.take_machine_code_error(), None);
+}
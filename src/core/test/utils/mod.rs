@@ -11,3 +11,107 @@ pub fn pretty_plane(plane: &[u128]) -> String {
   }
   output
 }
+
+// Decode the frames of a GIF from GifEncoder as (delay, RGB pixels), checking the structure along the way
+pub fn decode_gif(data: &[u8]) -> Vec<(u16, Vec<[u8; 3]>)> {
+    assert_eq!(&data[0 .. 6], b"GIF89a");
+    let _width = u16::from_le_bytes([data[6], data[7]]) as usize;
+    let _height = u16::from_le_bytes([data[8], data[9]]) as usize;
+    let mut pos = 13;
+    let mut delay = 0;
+    let mut frames = Vec::new();
+    loop {
+        match data[pos] {
+            0x21 => {
+                if data[pos + 1] == 0xF9 {
+                    delay = u16::from_le_bytes([data[pos + 4], data[pos + 5]]);
+                }
+                pos += 2;
+                while data[pos] != 0 {
+                    pos += data[pos] as usize + 1;
+                }
+                pos += 1;
+            }
+            0x2C => {
+                assert_eq!(u16::from_le_bytes([data[pos + 5], data[pos + 6]]) as usize, _width);
+                assert_eq!(u16::from_le_bytes([data[pos + 7], data[pos + 8]]) as usize, _height);
+                let _table_size = 2 << (data[pos + 9] & 0x07);
+                pos += 10;
+                let colors: Vec<[u8; 3]> = data[pos .. pos + _table_size * 3].chunks(3).map(|c| [c[0], c[1], c[2]]).collect();
+                pos += _table_size * 3;
+                let _min_code_size = data[pos];
+                pos += 1;
+                let mut codes = Vec::new();
+                while data[pos] != 0 {
+                    codes.extend_from_slice(&data[pos + 1 .. pos + 1 + data[pos] as usize]);
+                    pos += data[pos] as usize + 1;
+                }
+                pos += 1;
+                let indices = lzw_decode(&codes, _min_code_size);
+                assert_eq!(indices.len(), _width * _height);
+                frames.push((delay, indices.iter().map(|i| colors[*i as usize]).collect()));
+            }
+            0x3B => return frames,
+            other => panic!("Unexpected GIF block {:02X}", other)
+        }
+    }
+}
+
+// Decode the same frames with the gif crate, to check GifEncoder's output against a decoder that isn't ours
+pub fn decode_gif_external(data: &[u8]) -> Vec<(u16, Vec<[u8; 3]>)> {
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::RGBA);
+    let mut decoder = options.read_info(data).unwrap();
+    let mut frames = Vec::new();
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        frames.push((frame.delay, frame.buffer.chunks_exact(4).map(|c| [c[0], c[1], c[2]]).collect()));
+    }
+    frames
+}
+
+fn lzw_decode(data: &[u8], min_code_size: u8) -> Vec<u8> {
+    let _clear_code = 1_usize << min_code_size;
+    let mut table: Vec<Vec<u8>> = Vec::new();
+    let mut code_size = min_code_size + 1;
+    let mut output = Vec::new();
+    let mut prev: Option<usize> = None;
+    let mut bit = 0;
+    loop {
+        let mut code = 0;
+        for i in 0 .. code_size as usize {
+            code |= ((data[(bit + i) / 8] >> ((bit + i) % 8)) as usize & 1) << i;
+        }
+        bit += code_size as usize;
+        if code == _clear_code {
+            table = (0 .. _clear_code).map(|i| vec![i as u8]).collect();
+            table.push(Vec::new());
+            table.push(Vec::new());
+            code_size = min_code_size + 1;
+            prev = None;
+            continue;
+        }
+        if code == _clear_code + 1 {
+            return output;
+        }
+        let entry = match prev {
+            Some(prev) if code == table.len() => {
+                let mut entry = table[prev].clone();
+                entry.push(table[prev][0]);
+                entry
+            }
+            _ => table[code].clone()
+        };
+        if let Some(prev) = prev {
+            if table.len() < 4096 {
+                let mut new_entry = table[prev].clone();
+                new_entry.push(entry[0]);
+                table.push(new_entry);
+                if table.len() == 1 << code_size && code_size < 12 {
+                    code_size += 1;
+                }
+            }
+        }
+        output.extend_from_slice(&entry);
+        prev = Some(code);
+    }
+}
@@ -0,0 +1,142 @@
+// Recording the display as an animated GIF or as raw RGBA frames. Frames are captured as they're presented,
+// from the buffer planes with the active palette (display persistence isn't applied).
+// GIF frames are deduplicated: a frame that's the same as the last one just makes the last one last longer.
+// Raw frames are written for every frame, so they can be piped to an encoder expecting a constant frame rate.
+
+use super::gif::GifEncoder;
+use super::{Chip8, Target};
+use basic_emu_frontend::Core;
+use clap::ValueEnum;
+use wasm_bindgen::prelude::*;
+
+// GIF delays are in hundredths of a second, and most viewers slow down frames that are shorter than this
+const MIN_GIF_DELAY: u32 = 2;
+
+#[wasm_bindgen]
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq)]
+pub enum VideoFormat {
+    #[default]
+    Gif,
+    Raw // RGBA frames at the display's size, one after another
+}
+
+pub struct VideoRecording {
+    format: VideoFormat,
+    width: usize,
+    height: usize,
+    // Time since recording started, in seconds
+    time: f64,
+    // The last GIF frame and when it started (in hundredths of a second), it's written once its delay is known
+    held_frame: Option<Vec<[u8; 4]>>,
+    held_start: u32,
+    gif: GifEncoder,
+    raw: Vec<u8>
+}
+
+impl VideoRecording {
+    fn new(format: VideoFormat, width: usize, height: usize) -> VideoRecording {
+        VideoRecording {
+            format,
+            width,
+            height,
+            time: 0.0,
+            held_frame: None,
+            held_start: 0,
+            gif: GifEncoder::new(width, height),
+            raw: Vec::new()
+        }
+    }
+
+    fn capture(&mut self, frame: Vec<[u8; 4]>, frame_rate: f32) {
+        let _now = (self.time * 100.0).round() as u32;
+        self.time += 1.0 / frame_rate as f64;
+        if self.format == VideoFormat::Raw {
+            self.raw.extend(frame.iter().flatten());
+            return;
+        }
+        match &self.held_frame {
+            Some(held) if *held == frame => (),
+            // Frames that would be too short replace the held frame instead, which keeps the timing right
+            Some(_) if _now - self.held_start < MIN_GIF_DELAY => self.held_frame = Some(frame),
+            _ => {
+                self.write_held_frame(_now);
+                self.held_frame = Some(frame);
+                self.held_start = _now;
+            }
+        }
+    }
+
+    fn write_held_frame(&mut self, end: u32) {
+        if let Some(held) = self.held_frame.take() {
+            let _delay = (end - self.held_start).max(MIN_GIF_DELAY);
+            self.gif.write_frame(self.width, self.height, &held, _delay.min(u16::MAX as u32) as u16);
+        }
+    }
+
+    fn take_output(&mut self) -> Vec<u8> {
+        match self.format {
+            VideoFormat::Gif => self.gif.take_output(),
+            VideoFormat::Raw => std::mem::take(&mut self.raw)
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        match self.format {
+            VideoFormat::Gif => {
+                self.write_held_frame((self.time * 100.0).round() as u32);
+                self.gif.finish()
+            }
+            VideoFormat::Raw => self.raw
+        }
+    }
+}
+
+impl Chip8 {
+    // Record every frame from now on, at the current display size
+    pub fn start_video_recording(&mut self, format: VideoFormat) {
+        self.video = Some(VideoRecording::new(format, self.get_width(), self.get_height()));
+    }
+
+    pub fn is_recording_video(&self) -> bool {
+        self.video.is_some()
+    }
+
+    // Take the bytes recorded so far, recording carries on. For GIFs, the last frame is held until it changes.
+    pub fn take_video_recording(&mut self) -> Vec<u8> {
+        self.video.as_mut().map(VideoRecording::take_output).unwrap_or_default()
+    }
+
+    // Take the rest of the recording and stop recording
+    pub fn stop_video_recording(&mut self) -> Vec<u8> {
+        self.video.take().map(VideoRecording::finish).unwrap_or_default()
+    }
+
+    // Capture the frame that was just presented
+    pub(super) fn capture_frame(&mut self) {
+        let Some(video) = &self.video else {
+            return;
+        };
+        // Frames from a different display size (after changing targets) don't fit the recording
+        if video.width != self.get_width() || video.height != self.get_height() {
+            return;
+        }
+        let _frame = self.video_frame();
+        let _frame_rate = self.frame_rate;
+        if let Some(video) = &mut self.video {
+            video.capture(_frame, _frame_rate);
+        }
+    }
+
+    fn video_frame(&self) -> Vec<[u8; 4]> {
+        let _pixels = self.get_width() * self.get_height();
+        match self.target {
+            // These have their own colours
+            Target::MegaChip | Target::Chip8X => {
+                let mut frame = vec![0; _pixels * 4];
+                self.draw(&mut frame);
+                frame.chunks_exact(4).map(|pixel| [pixel[0], pixel[1], pixel[2], pixel[3]]).collect()
+            }
+            _ => (0 .. _pixels).map(|i| self.palette[self.color_index(&self.buffer_planes, i)]).collect()
+        }
+    }
+}
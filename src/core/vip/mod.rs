@@ -0,0 +1,260 @@
+// COSMAC VIP system emulation: a CDP1802 CPU, the CDP1861 video chip, 4K of RAM and the hex keypad.
+// This runs the original CHIP-8 interpreter as machine code, which has to be loaded into 0000-01FF
+// with set_vip_interpreter since it isn't included. The monitor ROM isn't emulated either.
+// Based on the RCA COSMAC VIP instruction manual (VIP-311) and the CDP1861 data sheet.
+
+use super::{Chip8, HEIGHT, PLANE_COUNT, WIDTH};
+use super::cdp1802::{Bus, Cdp1802};
+
+// The VIP runs the CPU at 1.76064 MHz, and a machine cycle is 8 clocks
+const CYCLES_PER_SECOND: f32 = 1_760_640.0 / 8.0;
+
+// CDP1861 timing, each frame is 262 lines of 14 machine cycles
+const CYCLES_PER_LINE: u32 = 14;
+pub const CYCLES_PER_FRAME: u32 = 262 * CYCLES_PER_LINE;
+// The 128 lines shown on screen, each one starts with 8 DMA cycles that read the 64 pixels from R0
+const FIRST_DISPLAY_LINE: u32 = 80;
+pub const DISPLAY_LINES: usize = 128;
+pub const DMA_CYCLES_PER_LINE: u32 = 8;
+// The interrupt comes 29 cycles before the first DMA cycle, so the interrupt routine can set up R0 in time
+const INTERRUPT_CYCLE: u32 = FIRST_DISPLAY_LINE * CYCLES_PER_LINE - 29;
+// EF1 is active for the 4 lines before the display starts and the last 4 lines of the display
+const EF1_LINES: [u32; 2] = [FIRST_DISPLAY_LINE - 4, FIRST_DISPLAY_LINE + DISPLAY_LINES as u32 - 4];
+
+// Cycles left for the interpreter each frame with Timing::Vip, the CPU is stopped during the display DMA
+pub const CYCLE_BUDGET: u32 = CYCLES_PER_FRAME - DISPLAY_LINES as u32 * DMA_CYCLES_PER_LINE;
+// Every CHIP-8 instruction goes through the interpreter's fetch and decode loop first
+const FETCH_CYCLES: u32 = 40;
+
+// Size of the RAM, it's mirrored up to 0x8000 where the monitor ROM starts
+const RAM_SIZE: usize = 0x1000;
+const ROM_START: u16 = 0x8000;
+
+// State of everything connected to the CPU
+pub struct VipIo {
+    // Whether the CDP1861 is on (INP 1) or off (OUT 1)
+    pub display_on: bool,
+    // Machine cycles since the start of the frame
+    pub cycle: u32,
+    // DMA cycles the CDP1861 has requested that haven't happened yet
+    pub dma_pending: u32,
+    // Whether the interrupt for this frame still needs to be handled
+    pub interrupt_pending: bool,
+    // The key being scanned with OUT 2, EF3 is active when it's pressed
+    pub key_latch: u8,
+    // Pixels for each displayed line, the first pixel is the highest bit
+    pub lines: [u64; DISPLAY_LINES],
+    // Keys pressed, copied from the Chip8 before each instruction
+    pub keys: [bool; 16]
+}
+
+pub struct VipState {
+    pub cpu: Cdp1802,
+    pub io: VipIo,
+    // The CHIP-8 interpreter, this gets copied into memory at 0000 on init/reset
+    pub interpreter: Vec<u8>
+}
+
+impl VipState {
+    pub fn new(interpreter: Vec<u8>) -> VipState {
+        let mut cpu = Cdp1802::new();
+        // When the monitor starts a program at 0000, R1.1 is left pointing at the last page of RAM
+        cpu.r[1] = (RAM_SIZE - 0x100) as u16;
+        VipState {
+            cpu,
+            io: VipIo {
+                display_on: false,
+                cycle: 0,
+                dma_pending: 0,
+                interrupt_pending: false,
+                key_latch: 0,
+                lines: [0; DISPLAY_LINES],
+                keys: [false; 16]
+            },
+            interpreter
+        }
+    }
+}
+
+struct VipBus<'a> {
+    mem: &'a mut [u8],
+    io: &'a mut VipIo
+}
+
+impl Bus for VipBus<'_> {
+    fn read(&mut self, addr: u16) -> u8 {
+        if addr >= ROM_START {
+            return 0;
+        }
+        self.mem[addr as usize % RAM_SIZE]
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        if addr < ROM_START {
+            self.mem[addr as usize % RAM_SIZE] = value;
+        }
+    }
+
+    fn input(&mut self, port: u8) -> u8 {
+        if port == 1 {
+            self.io.display_on = true;
+        }
+        0
+    }
+
+    fn output(&mut self, port: u8, value: u8) {
+        match port {
+            1 => self.io.display_on = false,
+            2 => self.io.key_latch = value & 0xF,
+            _ => ()
+        }
+    }
+
+    fn flag(&self, n: u8) -> bool {
+        let _line = self.io.cycle / CYCLES_PER_LINE;
+        match n {
+            1 => self.io.display_on && EF1_LINES.iter().any(|start| (*start .. start + 4).contains(&_line)),
+            3 => self.io.keys[self.io.key_latch as usize],
+            _ => false
+        }
+    }
+}
+
+impl Chip8 {
+    pub fn set_vip_interpreter(&mut self, interpreter: Vec<u8>) {
+        self.vip.interpreter = interpreter;
+        self.load_vip_interpreter();
+    }
+
+    pub(super) fn load_vip_interpreter(&mut self) {
+        let _len = self.vip.interpreter.len().min(self.target.start_address());
+        self.mem[.. _len].copy_from_slice(&self.vip.interpreter[.. _len]);
+    }
+
+    // Approximate machine cycles the VIP interpreter takes to run a CHIP-8 instruction, for Timing::Vip.
+    // Based on Laurence Scotford's disassembly of the interpreter, this has to be called before the instruction runs.
+    pub(super) fn vip_instruction_cycles(&self, op: u16) -> u32 {
+        let _n = (op & 0xF) as u32;
+        let _vx = self.r_v[((op & 0xF00) >> 8) as usize];
+        let _vy = self.r_v[((op & 0xF0) >> 4) as usize];
+        let _kk = (op & 0xFF) as u8;
+        // Taking a skip costs an extra 4 cycles
+        let _skip = |taken: bool| if taken { 4 } else { 0 };
+        FETCH_CYCLES + match op & 0xF000 {
+            0x0000 if op == 0x00E0 => 24 + 3078, // Clears the display page a byte at a time
+            0x0000 => 10,
+            0x1000 => 12,
+            0x2000 => 26,
+            0x3000 => 10 + _skip(_vx == _kk),
+            0x4000 => 10 + _skip(_vx != _kk),
+            0x5000 => 18 + _skip(_vx == _vy),
+            0x6000 => 6,
+            0x7000 => 10,
+            0x8000 if _n == 0 => 12,
+            0x8000 => 44, // The ALU operations are run from a small routine built on the stack
+            0x9000 => 18 + _skip(_vx != _vy),
+            0xA000 => 12,
+            0xB000 => 22,
+            0xC000 => 36,
+            // Each row is XORed a byte at a time, so sprites that aren't byte aligned touch twice as many bytes
+            0xD000 => 26 + _n * if _vx.is_multiple_of(8) { 46 } else { 70 },
+            0xE000 => 14 + _skip(self.curr_keys[(_vx & 0xF) as usize] == (_kk == 0x9E)),
+            _ => match _kk {
+                0x0A => 18, // Polls the keypad until a key is released
+                0x1E => 16,
+                0x29 => 16,
+                0x33 => 80 + 16 * (_vx / 100 + _vx / 10 % 10 + _vx % 10) as u32, // Counts each digit down by repeated subtraction
+                0x55 | 0x65 => 14 + 14 * (((op & 0xF00) >> 8) as u32 + 1),
+                _ => 10
+            }
+        }
+    }
+
+    // Run one CDP1802 instruction, along with any DMA and interrupt cycles before it.
+    // Returns whether a frame was finished while it ran.
+    pub(super) fn run_vip_inst(&mut self) -> bool {
+        self.vip.io.keys.copy_from_slice(&self.curr_keys[.. 16]);
+        let mut frame_complete = false;
+
+        // DMA happens between instructions, the CPU stops while the CDP1861 reads the line
+        while self.vip.io.dma_pending > 0 {
+            let _line = (self.vip.io.cycle / CYCLES_PER_LINE - FIRST_DISPLAY_LINE) as usize;
+            let _value = self.vip.cpu.dma_out(&mut VipBus { mem: &mut self.mem, io: &mut self.vip.io });
+            let _shift = (self.vip.io.dma_pending - 1) * 8;
+            self.vip.io.lines[_line] |= (_value as u64) << _shift;
+            self.vip.io.dma_pending -= 1;
+            frame_complete |= self.run_vip_cycles(1);
+        }
+
+        if self.vip.io.interrupt_pending && self.vip.cpu.ie {
+            self.vip.io.interrupt_pending = false;
+            self.vip.cpu.interrupt();
+            frame_complete |= self.run_vip_cycles(1);
+        }
+
+        let _cycles = self.vip.cpu.step(&mut VipBus { mem: &mut self.mem, io: &mut self.vip.io });
+        frame_complete |= self.run_vip_cycles(_cycles);
+
+        // The VIP beeps while Q is set, so it's treated like the sound timer for the buzzer
+        self.r_audio = self.vip.cpu.q as u8;
+        frame_complete
+    }
+
+    // Run whole frames, the VIP's speed is fixed so the clock isn't used.
+    // Instructions take several cycles, so the frame can end partway through one.
+    pub(super) fn run_vip_frame(&mut self) {
+        while !self.run_vip_inst() {}
+    }
+
+    // Advance the CDP1861 by some machine cycles, returns whether the frame ended during them
+    fn run_vip_cycles(&mut self, cycles: u32) -> bool {
+        let mut frame_complete = false;
+        for _i in 0 .. cycles {
+            self.run_audio(1.0 / CYCLES_PER_SECOND);
+            let io = &mut self.vip.io;
+            io.cycle += 1;
+            if io.cycle == CYCLES_PER_FRAME {
+                io.cycle = 0;
+                io.interrupt_pending = false;
+                self.present_vip_frame();
+                frame_complete = true;
+                continue;
+            }
+            let _line = io.cycle / CYCLES_PER_LINE;
+            if io.display_on && io.cycle == INTERRUPT_CYCLE {
+                io.interrupt_pending = true;
+            }
+            // The interrupt request only lasts until the display starts, it's missed if interrupts are disabled until then
+            if io.cycle == FIRST_DISPLAY_LINE * CYCLES_PER_LINE {
+                io.interrupt_pending = false;
+            }
+            if io.display_on && io.cycle.is_multiple_of(CYCLES_PER_LINE) && (FIRST_DISPLAY_LINE .. FIRST_DISPLAY_LINE + DISPLAY_LINES as u32).contains(&_line) {
+                io.lines[(_line - FIRST_DISPLAY_LINE) as usize] = 0;
+                io.dma_pending = DMA_CYCLES_PER_LINE;
+            }
+        }
+        frame_complete
+    }
+
+    // Show the lines the CDP1861 read during the last frame, however the interpreter's interrupt routine laid them out
+    // (the CHIP-8 interpreter repeats each row of the display page for 4 lines).
+    // The 128 lines of 64 pixels don't fit in the 64 rows of a plane, so each row holds two lines, see vip_color_index.
+    fn present_vip_frame(&mut self) {
+        self.active_planes = [[0; HEIGHT]; PLANE_COUNT];
+        if self.vip.io.display_on {
+            for (y, row) in self.active_planes[0].iter_mut().enumerate() {
+                *row = ((self.vip.io.lines[y * 2] as u128) << 64) | self.vip.io.lines[y * 2 + 1] as u128;
+            }
+        }
+        self.present_frame();
+        self.capture_frame();
+    }
+
+    // Palette index of the pixel at index i of the 128x128 output frame, each line is one row of the frame
+    // with its pixels doubled horizontally. Even lines are in the high half of a plane row and odd lines in the low half.
+    pub(super) fn vip_color_index(&self, planes: &[[u128; HEIGHT]; PLANE_COUNT], i: usize) -> usize {
+        let (x, line) = (i % WIDTH / 2, i / WIDTH);
+        let _bit = 127 - (line % 2) * 64 - x;
+        ((planes[0][line / 2] >> _bit) & 1) as usize
+    }
+}
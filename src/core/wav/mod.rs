@@ -0,0 +1,84 @@
+// Writes audio as a WAV file of 32 bit float samples, so recordings hold exactly what the audio path produced.
+// The header is written with empty sizes at the start, and finish fills them in once the number of samples is known.
+
+use std::io::{Cursor, Result, Seek, SeekFrom, Write};
+
+const HEADER_SIZE: u32 = 58;
+const BYTES_PER_SAMPLE: u32 = 4;
+
+pub struct WavWriter<W: Write + Seek> {
+    writer: W,
+    num_channels: u16,
+    sample_rate: u32,
+    num_samples: u32
+}
+
+impl<W: Write + Seek> WavWriter<W> {
+    pub fn new(writer: W, num_channels: usize, sample_rate: u32) -> Result<WavWriter<W>> {
+        let mut wav = WavWriter {
+            writer,
+            num_channels: num_channels.max(1) as u16,
+            sample_rate,
+            num_samples: 0
+        };
+        wav.write_header()?;
+        Ok(wav)
+    }
+
+    // Append interleaved samples
+    pub fn write_samples(&mut self, samples: &[f32]) -> Result<()> {
+        let mut bytes = Vec::with_capacity(samples.len() * BYTES_PER_SAMPLE as usize);
+        for sample in samples {
+            bytes.extend_from_slice(&sample.to_le_bytes());
+        }
+        self.writer.write_all(&bytes)?;
+        self.num_samples += samples.len() as u32;
+        Ok(())
+    }
+
+    // Fill in the sizes in the header and flush, more samples can still be written after this
+    pub fn finish(&mut self) -> Result<()> {
+        self.write_header()?;
+        self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_header(&mut self) -> Result<()> {
+        let _data_size = self.num_samples * BYTES_PER_SAMPLE;
+        let _block_align = self.num_channels as u32 * BYTES_PER_SAMPLE;
+        let mut header = Vec::with_capacity(HEADER_SIZE as usize);
+        header.extend_from_slice(b"RIFF");
+        header.extend_from_slice(&(HEADER_SIZE - 8 + _data_size).to_le_bytes());
+        header.extend_from_slice(b"WAVE");
+        // Format chunk, format 3 is IEEE float
+        header.extend_from_slice(b"fmt ");
+        header.extend_from_slice(&18_u32.to_le_bytes());
+        header.extend_from_slice(&3_u16.to_le_bytes());
+        header.extend_from_slice(&self.num_channels.to_le_bytes());
+        header.extend_from_slice(&self.sample_rate.to_le_bytes());
+        header.extend_from_slice(&(self.sample_rate * _block_align).to_le_bytes());
+        header.extend_from_slice(&(_block_align as u16).to_le_bytes());
+        header.extend_from_slice(&(BYTES_PER_SAMPLE as u16 * 8).to_le_bytes());
+        header.extend_from_slice(&0_u16.to_le_bytes());
+        // Non-PCM formats also need the number of sample frames
+        header.extend_from_slice(b"fact");
+        header.extend_from_slice(&4_u32.to_le_bytes());
+        header.extend_from_slice(&(self.num_samples / self.num_channels as u32).to_le_bytes());
+        header.extend_from_slice(b"data");
+        header.extend_from_slice(&_data_size.to_le_bytes());
+        self.writer.seek(SeekFrom::Start(0))?;
+        self.writer.write_all(&header)
+    }
+}
+
+// Encode interleaved samples as a complete WAV file in memory
+pub fn encode_wav(samples: &[f32], num_channels: usize, sample_rate: u32) -> Vec<u8> {
+    let mut wav = WavWriter::new(Cursor::new(Vec::new()), num_channels, sample_rate).unwrap();
+    wav.write_samples(samples).unwrap();
+    wav.finish().unwrap();
+    wav.into_inner().into_inner()
+}
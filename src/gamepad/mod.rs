@@ -0,0 +1,130 @@
+// Gamepad input. Button presses arrive as GamepadEvents on a channel, so they can come from a real controller
+// (with the gamepad feature, which reads them with gilrs on its own thread) or be sent by tests.
+// The left stick acts like a second D-pad.
+
+use clap::ValueEnum;
+use std::sync::mpsc::Receiver;
+
+// How far the stick has to be pushed to count as a direction being pressed, out of 1
+#[cfg(feature = "gamepad")]
+const STICK_THRESHOLD: f32 = 0.5;
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GamepadButton {
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+    LeftStickUp,
+    LeftStickDown,
+    LeftStickLeft,
+    LeftStickRight,
+    South, // A on Xbox controllers, cross on PlayStation controllers
+    East,
+    North,
+    West,
+    LeftShoulder,
+    RightShoulder,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start
+}
+
+// Only sent by tests without the gamepad feature
+#[cfg_attr(not(feature = "gamepad"), allow(dead_code))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GamepadEvent {
+    Pressed(GamepadButton),
+    Released(GamepadButton)
+}
+
+impl GamepadButton {
+    // Buttons are named like the variants (case insensitive), e.g. DPadUp or South
+    pub fn from_name(name: &str) -> Option<GamepadButton> {
+        GamepadButton::value_variants().iter().copied().find(|button| format!("{:?}", button).eq_ignore_ascii_case(name))
+    }
+}
+
+// Start reading controllers on another thread, returning the channel their events are sent on
+#[cfg(feature = "gamepad")]
+pub fn connect() -> Receiver<GamepadEvent> {
+    use gilrs::{Axis, EventType, Gilrs};
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let mut gilrs = match Gilrs::new() {
+            Ok(gilrs) => gilrs,
+            Err(error) => {
+                eprintln!("Problem opening gamepads: {error:?}");
+                return;
+            }
+        };
+        // Which way the stick is pushed on each axis, -1, 0 or 1
+        let mut stick = [0_i8; 2];
+        loop {
+            while let Some(gilrs::Event { event, .. }) = gilrs.next_event() {
+                let mut events = Vec::new();
+                match event {
+                    EventType::ButtonPressed(button, _) => events.extend(map_button(button).map(GamepadEvent::Pressed)),
+                    EventType::ButtonReleased(button, _) => events.extend(map_button(button).map(GamepadEvent::Released)),
+                    EventType::AxisChanged(axis @ (Axis::LeftStickX | Axis::LeftStickY), value, _) => {
+                        let _axis = (axis == Axis::LeftStickY) as usize;
+                        let _direction = if value > STICK_THRESHOLD { 1 } else if value < -STICK_THRESHOLD { -1 } else { 0 };
+                        // gilrs has up as positive Y
+                        let _buttons = match _axis {
+                            0 => [GamepadButton::LeftStickLeft, GamepadButton::LeftStickRight],
+                            _ => [GamepadButton::LeftStickDown, GamepadButton::LeftStickUp]
+                        };
+                        if _direction != stick[_axis] {
+                            if stick[_axis] != 0 {
+                                events.push(GamepadEvent::Released(_buttons[(stick[_axis] > 0) as usize]));
+                            }
+                            if _direction != 0 {
+                                events.push(GamepadEvent::Pressed(_buttons[(_direction > 0) as usize]));
+                            }
+                            stick[_axis] = _direction;
+                        }
+                    }
+                    _ => ()
+                }
+                for event in events {
+                    if sender.send(event).is_err() {
+                        return;
+                    }
+                }
+            }
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+    });
+    receiver
+}
+
+#[cfg(feature = "gamepad")]
+fn map_button(button: gilrs::Button) -> Option<GamepadButton> {
+    use gilrs::Button;
+
+    match button {
+        Button::DPadUp => Some(GamepadButton::DPadUp),
+        Button::DPadDown => Some(GamepadButton::DPadDown),
+        Button::DPadLeft => Some(GamepadButton::DPadLeft),
+        Button::DPadRight => Some(GamepadButton::DPadRight),
+        Button::South => Some(GamepadButton::South),
+        Button::East => Some(GamepadButton::East),
+        Button::North => Some(GamepadButton::North),
+        Button::West => Some(GamepadButton::West),
+        Button::LeftTrigger => Some(GamepadButton::LeftShoulder),
+        Button::RightTrigger => Some(GamepadButton::RightShoulder),
+        Button::LeftTrigger2 => Some(GamepadButton::LeftTrigger),
+        Button::RightTrigger2 => Some(GamepadButton::RightTrigger),
+        Button::Select => Some(GamepadButton::Select),
+        Button::Start => Some(GamepadButton::Start),
+        _ => None
+    }
+}
+
+// Without the gamepad feature there are no controllers, so nothing is ever sent
+#[cfg(not(feature = "gamepad"))]
+pub fn connect() -> Receiver<GamepadEvent> {
+    std::sync::mpsc::channel().1
+}
@@ -0,0 +1,397 @@
+// Host keyboard and gamepad mappings for the keypads. A keymap starts from a preset and can be changed by keymap files,
+// which bind any number of host keys and gamepad buttons to each CHIP-8 key.
+//
+// Keymap files have one binding per line, a CHIP-8 key in hex (10-1F for the second CHIP-8X keypad) or a hotkey
+// (record-video), then the host key or list of host keys to use for it. Keys that aren't listed keep their current binding.
+//     # Arrows as well as WASD-style keys for a game using 5/7/8/9, and the gamepad's South button for 6
+//     5 = ["W", "Up"]
+//     7 = ["A", "Left"]
+//     8 = ["S", "Down"]
+//     9 = ["D", "Right"]
+//     6 = ["E", "South"]
+//     record-video = "F12"
+
+use crate::config::ConfigError;
+use crate::gamepad::{GamepadButton, GamepadEvent};
+use basic_emu_frontend::{Core, VirtualKeyCode, VirtualKeyCode as K};
+use clap::ValueEnum;
+use std::path::Path;
+use std::sync::mpsc::Receiver;
+
+#[cfg(test)]
+mod test;
+
+// CHIP-8 keypad layout, which presets bind from left to right and top to bottom
+//     1 2 3 C
+//     4 5 6 D
+//     7 8 9 E
+//     A 0 B F
+const KEYPAD_ORDER: [usize; 16] = [0x1, 0x2, 0x3, 0xC, 0x4, 0x5, 0x6, 0xD, 0x7, 0x8, 0x9, 0xE, 0xA, 0x0, 0xB, 0xF];
+// The second CHIP-8X keypad is keys 0x10-0x1F
+const SECOND_KEYPAD: usize = 0x10;
+
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq)]
+pub enum KeymapPreset {
+    #[default]
+    Qwerty, // 1234/QWER/ASDF/ZXCV, with the second CHIP-8X keypad on 7890/UIOP/JKL;/M,./
+    Azerty, // 1234/AZER/QSDF/WXCV, with the second CHIP-8X keypad on the numpad
+    Qwertz, // 1234/QWER/ASDF/YXCV, with the second CHIP-8X keypad on the numpad
+    Dvorak, // 1234/',.P/AOEU/;QJK, with the second CHIP-8X keypad on the numpad
+    Numpad // Digits on the same numpad digits and A-F on / * - + Enter ., with the second CHIP-8X keypad on 1234/QWER/ASDF/ZXCV
+}
+
+const QWERTY_LEFT: [VirtualKeyCode; 16] = [
+    K::Key1, K::Key2, K::Key3, K::Key4,
+    K::Q, K::W, K::E, K::R,
+    K::A, K::S, K::D, K::F,
+    K::Z, K::X, K::C, K::V
+];
+
+const QWERTY_RIGHT: [VirtualKeyCode; 16] = [
+    K::Key7, K::Key8, K::Key9, K::Key0,
+    K::U, K::I, K::O, K::P,
+    K::J, K::K, K::L, K::Semicolon,
+    K::M, K::Comma, K::Period, K::Slash
+];
+
+const AZERTY_LEFT: [VirtualKeyCode; 16] = [
+    K::Key1, K::Key2, K::Key3, K::Key4,
+    K::A, K::Z, K::E, K::R,
+    K::Q, K::S, K::D, K::F,
+    K::W, K::X, K::C, K::V
+];
+
+const QWERTZ_LEFT: [VirtualKeyCode; 16] = [
+    K::Key1, K::Key2, K::Key3, K::Key4,
+    K::Q, K::W, K::E, K::R,
+    K::A, K::S, K::D, K::F,
+    K::Y, K::X, K::C, K::V
+];
+
+const DVORAK_LEFT: [VirtualKeyCode; 16] = [
+    K::Key1, K::Key2, K::Key3, K::Key4,
+    K::Apostrophe, K::Comma, K::Period, K::P,
+    K::A, K::O, K::E, K::U,
+    K::Semicolon, K::Q, K::J, K::K
+];
+
+// Numpad keys by their label, so the numpad's digits match the CHIP-8 keypad's
+const NUMPAD: [VirtualKeyCode; 16] = [
+    K::Numpad1, K::Numpad2, K::Numpad3, K::NumpadSubtract,
+    K::Numpad4, K::Numpad5, K::Numpad6, K::NumpadAdd,
+    K::Numpad7, K::Numpad8, K::Numpad9, K::NumpadEnter,
+    K::NumpadDivide, K::Numpad0, K::NumpadMultiply, K::NumpadDecimal
+];
+
+// Gamepad buttons for every preset, the D-pad and stick on 5/7/8/9 with 6 and 4 next to them, like WASD, E and Q
+const GAMEPAD: [(GamepadButton, usize); 10] = [
+    (GamepadButton::DPadUp, 0x5),
+    (GamepadButton::DPadLeft, 0x7),
+    (GamepadButton::DPadDown, 0x8),
+    (GamepadButton::DPadRight, 0x9),
+    (GamepadButton::LeftStickUp, 0x5),
+    (GamepadButton::LeftStickLeft, 0x7),
+    (GamepadButton::LeftStickDown, 0x8),
+    (GamepadButton::LeftStickRight, 0x9),
+    (GamepadButton::South, 0x6),
+    (GamepadButton::East, 0x4)
+];
+
+// A keyboard key or a gamepad button
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HostKey {
+    Key(VirtualKeyCode),
+    Button(GamepadButton)
+}
+
+// Frontend actions that run when their host key is pressed, instead of pressing a key in the core
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Hotkey {
+    RecordVideo // Start or stop recording video
+}
+
+// What a host key does, press a key on the keypads or run a hotkey
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Binding {
+    Keypad(usize),
+    Hotkey(Hotkey)
+}
+
+// Cores the KeyRouter can pass hotkeys on to
+pub trait Hotkeys {
+    fn hotkey(&mut self, hotkey: Hotkey);
+}
+
+impl KeymapPreset {
+    // Host keys for the first and second keypads, in KEYPAD_ORDER
+    fn keypads(&self) -> ([VirtualKeyCode; 16], [VirtualKeyCode; 16]) {
+        match self {
+            KeymapPreset::Qwerty => (QWERTY_LEFT, QWERTY_RIGHT),
+            KeymapPreset::Azerty => (AZERTY_LEFT, NUMPAD),
+            KeymapPreset::Qwertz => (QWERTZ_LEFT, NUMPAD),
+            KeymapPreset::Dvorak => (DVORAK_LEFT, NUMPAD),
+            KeymapPreset::Numpad => (NUMPAD, QWERTY_LEFT)
+        }
+    }
+}
+
+// Host keys and gamepad buttons, and what each one does
+pub struct HostKeymap {
+    bindings: Vec<(VirtualKeyCode, Binding)>,
+    buttons: Vec<(GamepadButton, Binding)>
+}
+
+impl HostKeymap {
+    pub fn new(preset: KeymapPreset) -> HostKeymap {
+        let (first, second) = preset.keypads();
+        let mut bindings = Vec::new();
+        for (i, key) in KEYPAD_ORDER.iter().enumerate() {
+            bindings.push((first[i], Binding::Keypad(*key)));
+            bindings.push((second[i], Binding::Keypad(SECOND_KEYPAD + *key)));
+        }
+        bindings.push((K::F9, Binding::Hotkey(Hotkey::RecordVideo)));
+        HostKeymap {
+            bindings,
+            buttons: GAMEPAD.iter().map(|(button, key)| (*button, Binding::Keypad(*key))).collect()
+        }
+    }
+
+    // Replace the host keys of each key bound in a keymap file
+    pub fn load_file(&mut self, path: &str) -> Result<(), ConfigError> {
+        let _text = std::fs::read_to_string(path).map_err(|error| ConfigError::Open(path.to_string(), error))?;
+        self.load_str(&_text).map_err(|error| ConfigError::Parse(path.to_string(), error))
+    }
+
+    // Load the keymap file for a ROM if there is one, this is the ROM's path with a .keymap extension
+    pub fn load_rom_file(&mut self, rom_path: &str) -> Result<(), ConfigError> {
+        let _path = Path::new(rom_path).with_extension("keymap");
+        match _path.is_file() {
+            true => self.load_file(&_path.to_string_lossy()),
+            false => Ok(())
+        }
+    }
+
+    pub fn load_str(&mut self, text: &str) -> Result<(), String> {
+        for (line_number, line) in text.lines().enumerate() {
+            let _line = line.split('#').next().unwrap().trim();
+            if _line.is_empty() {
+                continue;
+            }
+            let _error = |message: &str| format!("line {}: {}", line_number + 1, message);
+            let Some((name, value)) = _line.split_once('=') else {
+                return Err(_error("expected <key> = <host keys>"));
+            };
+            let _binding = parse_binding(name.trim()).ok_or_else(|| _error(&format!("unknown CHIP-8 key {}", name.trim())))?;
+            let _host_keys = parse_host_keys(value.trim()).map_err(|message| _error(&message))?;
+            self.bind(_binding, &_host_keys);
+        }
+        Ok(())
+    }
+
+    // Bind host keys to a key or hotkey, replacing its old ones. A host key can only do one thing,
+    // so it's taken from any other key.
+    pub fn bind(&mut self, binding: Binding, host_keys: &[HostKey]) {
+        self.bindings.retain(|(key, bound)| *bound != binding && !host_keys.contains(&HostKey::Key(*key)));
+        self.buttons.retain(|(button, bound)| *bound != binding && !host_keys.contains(&HostKey::Button(*button)));
+        for host_key in host_keys {
+            match host_key {
+                HostKey::Key(key) => self.bindings.push((*key, binding)),
+                HostKey::Button(button) => self.buttons.push((*button, binding))
+            }
+        }
+    }
+
+    // Bind a gamepad button to a key, leaving the key's other host keys and buttons alone
+    pub fn bind_button(&mut self, button: GamepadButton, index: usize) {
+        self.buttons.retain(|(bound, _binding)| *bound != button);
+        self.buttons.push((button, Binding::Keypad(index)));
+    }
+
+    // The host keys for the frontend's keymap
+    pub fn host_keys(&self) -> Vec<VirtualKeyCode> {
+        self.bindings.iter().map(|(key, _binding)| *key).collect()
+    }
+
+    // What each of the host keys does
+    pub fn bindings(&self) -> Vec<Binding> {
+        self.bindings.iter().map(|(_key, binding)| *binding).collect()
+    }
+
+    // The gamepad buttons and what each one does
+    pub fn buttons(&self) -> Vec<(GamepadButton, Binding)> {
+        self.buttons.clone()
+    }
+}
+
+fn parse_binding(name: &str) -> Option<Binding> {
+    if name.eq_ignore_ascii_case("record-video") {
+        return Some(Binding::Hotkey(Hotkey::RecordVideo));
+    }
+    match usize::from_str_radix(name, 16) {
+        Ok(index) if index < SECOND_KEYPAD * 2 => Some(Binding::Keypad(index)),
+        _ => None
+    }
+}
+
+// A quoted host key or gamepad button name, or a list of them in square brackets
+fn parse_host_keys(value: &str) -> Result<Vec<HostKey>, String> {
+    let _list = match value.strip_prefix('[').and_then(|list| list.strip_suffix(']')) {
+        Some(list) => list,
+        None => value
+    };
+    _list.split(',').map(str::trim).filter(|name| !name.is_empty()).map(|name| {
+        let _unquoted = name.strip_prefix('"').and_then(|name| name.strip_suffix('"'));
+        let _name = _unquoted.ok_or_else(|| format!("expected a quoted host key name, found {}", name))?;
+        match parse_host_key(_name) {
+            Some(key) => Ok(HostKey::Key(key)),
+            None => GamepadButton::from_name(_name).map(HostKey::Button).ok_or_else(|| format!("unknown host key {}", _name))
+        }
+    }).collect()
+}
+
+macro_rules! host_key_names {
+    ($($key:ident),*) => {
+        // Host keys are named like the VirtualKeyCode variants (case insensitive), and digits can be written without Key
+        fn parse_host_key(name: &str) -> Option<VirtualKeyCode> {
+            let _name = match name.len() == 1 && name.as_bytes()[0].is_ascii_digit() {
+                true => format!("Key{}", name),
+                false => name.to_string()
+            };
+            $(
+                if _name.eq_ignore_ascii_case(stringify!($key)) {
+                    return Some(VirtualKeyCode::$key);
+                }
+            )*
+            None
+        }
+    };
+}
+
+host_key_names!(
+    Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, Key0,
+    A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+    Escape, Insert, Home, Delete, End, PageDown, PageUp, Left, Up, Right, Down, Back, Return, Space, Tab,
+    Numpad0, Numpad1, Numpad2, Numpad3, Numpad4, Numpad5, Numpad6, Numpad7, Numpad8, Numpad9,
+    NumpadAdd, NumpadDivide, NumpadDecimal, NumpadEnter, NumpadMultiply, NumpadSubtract,
+    Apostrophe, Backslash, Comma, Equals, Grave, LBracket, RBracket, Minus, Period, Semicolon, Slash,
+    LAlt, RAlt, LControl, RControl, LShift, RShift
+);
+
+// Passes key presses from the frontend's keymap and gamepad events on to the core's key indices, or runs their hotkeys.
+// A key stays pressed while any of the host keys or buttons bound to it are held.
+pub struct KeyRouter<C: Core + Hotkeys> {
+    core: C,
+    bindings: Vec<Binding>,
+    held: Vec<bool>,
+    buttons: Vec<(GamepadButton, Binding)>,
+    buttons_held: Vec<bool>,
+    gamepad: Receiver<GamepadEvent>
+}
+
+impl<C: Core + Hotkeys> KeyRouter<C> {
+    pub fn new(core: C, keymap: &HostKeymap, gamepad: Receiver<GamepadEvent>) -> KeyRouter<C> {
+        let bindings = keymap.bindings();
+        let buttons = keymap.buttons();
+        KeyRouter {
+            core,
+            held: vec![false; bindings.len()],
+            bindings,
+            buttons_held: vec![false; buttons.len()],
+            buttons,
+            gamepad
+        }
+    }
+
+    fn any_held(&self, binding: Binding) -> bool {
+        self.bindings.iter().zip(self.held.iter()).any(|(bound, held)| *bound == binding && *held)
+            || self.buttons.iter().zip(self.buttons_held.iter()).any(|((_button, bound), held)| *bound == binding && *held)
+    }
+
+    // Set whether host key (or button) i is held, pressing or releasing its key in the core if that changes it.
+    // Hotkeys run once when they're first pressed.
+    fn hold(&mut self, button: bool, i: usize, pressed: bool) {
+        let _binding = if button { self.buttons[i].1 } else { self.bindings[i] };
+        let _was_held = self.any_held(_binding);
+        if button {
+            self.buttons_held[i] = pressed;
+        } else {
+            self.held[i] = pressed;
+        }
+        match (_binding, _was_held, self.any_held(_binding)) {
+            (Binding::Keypad(index), false, true) => self.core.press_key(index),
+            (Binding::Keypad(index), true, false) => self.core.release_key(index),
+            (Binding::Hotkey(hotkey), false, true) => self.core.hotkey(hotkey),
+            _ => ()
+        }
+    }
+
+    fn poll_gamepad(&mut self) {
+        while let Ok(event) = self.gamepad.try_recv() {
+            let (_button, _pressed) = match event {
+                GamepadEvent::Pressed(button) => (button, true),
+                GamepadEvent::Released(button) => (button, false)
+            };
+            for i in 0 .. self.buttons.len() {
+                if self.buttons[i].0 == _button {
+                    self.hold(true, i, _pressed);
+                }
+            }
+        }
+    }
+}
+
+impl<C: Core + Hotkeys> Core for KeyRouter<C> {
+    fn get_width(&self) -> usize {
+        self.core.get_width()
+    }
+
+    fn get_height(&self) -> usize {
+        self.core.get_height()
+    }
+
+    fn set_num_output_channels(&mut self, value: usize) {
+        self.core.set_num_output_channels(value);
+    }
+
+    fn set_seconds_per_output_sample(&mut self, value: f32) {
+        self.core.set_seconds_per_output_sample(value);
+    }
+
+    fn run_inst(&mut self) {
+        self.poll_gamepad();
+        self.core.run_inst();
+    }
+
+    fn run_frame(&mut self) {
+        self.poll_gamepad();
+        self.core.run_frame();
+    }
+
+    fn get_sample_queue_length(&self) -> usize {
+        self.core.get_sample_queue_length()
+    }
+
+    fn get_sample(&mut self) -> f32 {
+        self.core.get_sample()
+    }
+
+    fn press_key(&mut self, key_index: usize) {
+        self.hold(false, key_index, true);
+    }
+
+    fn release_key(&mut self, key_index: usize) {
+        self.hold(false, key_index, false);
+    }
+
+    fn get_key_pressed(&self, key_index: usize) -> bool {
+        match self.bindings[key_index] {
+            Binding::Keypad(index) => self.core.get_key_pressed(index),
+            Binding::Hotkey(_hotkey) => self.held[key_index]
+        }
+    }
+
+    fn draw(&self, frame: &mut [u8]) {
+        self.core.draw(frame);
+    }
+}
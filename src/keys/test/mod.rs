@@ -0,0 +1,175 @@
+use super::{Binding, HostKey, HostKeymap, Hotkey, Hotkeys, KeyRouter, KeymapPreset};
+use super::Binding::Keypad;
+use crate::gamepad::{GamepadButton, GamepadEvent};
+use crate::core::{self, VideoFormat};
+use crate::recorder::Recorder;
+use basic_emu_frontend::{Core, VirtualKeyCode as K};
+
+fn binding(keymap: &HostKeymap, key: K) -> Option<Binding> {
+    keymap.host_keys().iter().position(|host_key| *host_key == key).map(|i| keymap.bindings()[i])
+}
+
+fn router(chip8: core::Chip8, keymap: &HostKeymap, gamepad: std::sync::mpsc::Receiver<GamepadEvent>) -> KeyRouter<Recorder> {
+    KeyRouter::new(Recorder::new(chip8, None, None, VideoFormat::Gif).unwrap(), keymap, gamepad)
+}
+
+#[test]
+fn test_keymap_presets() {
+    let qwerty = HostKeymap::new(KeymapPreset::Qwerty);
+    assert_eq!(binding(&qwerty, K::X), Some(Keypad(0x0)));
+    assert_eq!(binding(&qwerty, K::Key4), Some(Keypad(0xC)));
+    assert_eq!(binding(&qwerty, K::V), Some(Keypad(0xF)));
+    assert_eq!(binding(&qwerty, K::Slash), Some(Keypad(0x1F)));
+    assert_eq!(binding(&qwerty, K::F9), Some(Binding::Hotkey(Hotkey::RecordVideo)));
+
+    let azerty = HostKeymap::new(KeymapPreset::Azerty);
+    assert_eq!(binding(&azerty, K::A), Some(Keypad(0x4)));
+    assert_eq!(binding(&azerty, K::Q), Some(Keypad(0x7)));
+    assert_eq!(binding(&azerty, K::Numpad0), Some(Keypad(0x10)));
+
+    let numpad = HostKeymap::new(KeymapPreset::Numpad);
+    for digit in 0 .. 10 {
+        let _key = [K::Numpad0, K::Numpad1, K::Numpad2, K::Numpad3, K::Numpad4, K::Numpad5, K::Numpad6, K::Numpad7, K::Numpad8, K::Numpad9][digit];
+        assert_eq!(binding(&numpad, _key), Some(Keypad(digit)));
+    }
+
+    // Every preset binds all 32 keypad keys and the hotkey to different host keys
+    for preset in [KeymapPreset::Qwerty, KeymapPreset::Azerty, KeymapPreset::Qwertz, KeymapPreset::Dvorak, KeymapPreset::Numpad] {
+        let keymap = HostKeymap::new(preset);
+        let mut indices: Vec<usize> = keymap.bindings().iter().filter_map(|binding| match binding {
+            Keypad(index) => Some(*index),
+            Binding::Hotkey(_hotkey) => None
+        }).collect();
+        indices.sort();
+        assert_eq!(indices, (0 .. 32).collect::<Vec<usize>>());
+        assert!(keymap.bindings().contains(&Binding::Hotkey(Hotkey::RecordVideo)));
+        let _host_keys = keymap.host_keys();
+        assert!(_host_keys.iter().all(|key| _host_keys.iter().filter(|other| *other == key).count() == 1));
+    }
+}
+
+#[test]
+fn test_keymap_file() {
+    let mut keymap = HostKeymap::new(KeymapPreset::Qwerty);
+    let _file = "# WASD and arrows\n5 = [\"W\", \"up\"]\n8 = [\"S\", \"Down\"]  # S was 8 already\n1a = \"Q\"\nrecord-video = \"F12\"\n";
+    assert_eq!(keymap.load_str(_file), Ok(()));
+    assert_eq!(binding(&keymap, K::W), Some(Keypad(0x5)));
+    assert_eq!(binding(&keymap, K::Up), Some(Keypad(0x5)));
+    assert_eq!(binding(&keymap, K::Down), Some(Keypad(0x8)));
+    assert_eq!(binding(&keymap, K::S), Some(Keypad(0x8)));
+    // Q was key 4, and now only presses 1A
+    assert_eq!(binding(&keymap, K::Q), Some(Keypad(0x1A)));
+    assert!(!keymap.bindings().contains(&Keypad(0x4)));
+    assert_eq!(binding(&keymap, K::M), None);
+    assert_eq!(binding(&keymap, K::F9), None);
+    assert_eq!(binding(&keymap, K::F12), Some(Binding::Hotkey(Hotkey::RecordVideo)));
+
+    assert_eq!(keymap.load_str("5 = \"W\"\n\n20 = \"A\""), Err(String::from("line 3: unknown CHIP-8 key 20")));
+    assert_eq!(keymap.load_str("5 = \"Whoops\""), Err(String::from("line 1: unknown host key Whoops")));
+    assert_eq!(keymap.load_str("5 = W"), Err(String::from("line 1: expected a quoted host key name, found W")));
+    assert_eq!(keymap.load_str("5"), Err(String::from("line 1: expected <key> = <host keys>")));
+}
+
+#[test]
+fn test_key_router() {
+    // Key 5 stays pressed while either of its host keys is held
+    let mut keymap = HostKeymap::new(KeymapPreset::Qwerty);
+    keymap.bind(Keypad(0x5), &[HostKey::Key(K::W), HostKey::Key(K::Up)]);
+    let _w = keymap.host_keys().iter().position(|key| *key == K::W).unwrap();
+    let _up = keymap.host_keys().iter().position(|key| *key == K::Up).unwrap();
+    let mut router = router(core::Chip8::new(core::Target::Chip, 10, vec![]), &keymap, std::sync::mpsc::channel().1);
+    router.press_key(_w);
+    router.press_key(_up);
+    router.release_key(_w);
+    assert!(router.get_key_pressed(_up));
+    assert!(router.get_key_pressed(_w));
+    router.press_key(_up);
+    router.release_key(_up);
+    assert!(!router.get_key_pressed(_up));
+}
+
+#[test]
+fn test_gamepad() {
+    assert_eq!(GamepadButton::from_name("dpadup"), Some(GamepadButton::DPadUp));
+    assert_eq!(GamepadButton::from_name("South"), Some(GamepadButton::South));
+    assert_eq!(GamepadButton::from_name("Middle"), None);
+
+    let mut keymap = HostKeymap::new(KeymapPreset::Qwerty);
+    assert_eq!(keymap.load_str("6 = [\"E\", \"South\"]"), Ok(()));
+    assert!(keymap.buttons().contains(&(GamepadButton::South, Keypad(0x6))));
+    assert!(keymap.buttons().contains(&(GamepadButton::DPadUp, Keypad(0x5))));
+    let _w = keymap.host_keys().iter().position(|key| *key == K::W).unwrap();
+    let _e = keymap.host_keys().iter().position(|key| *key == K::E).unwrap();
+
+    // Events are picked up when the core runs, and keys stay pressed while a host key or a button is held
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let mut router = router(core::Chip8::new(core::Target::Chip, 10, vec![0x12, 0x00]), &keymap, receiver);
+    sender.send(GamepadEvent::Pressed(GamepadButton::DPadUp)).unwrap();
+    assert!(!router.get_key_pressed(_w));
+    router.run_frame();
+    assert!(router.get_key_pressed(_w));
+    sender.send(GamepadEvent::Pressed(GamepadButton::LeftStickUp)).unwrap();
+    sender.send(GamepadEvent::Released(GamepadButton::DPadUp)).unwrap();
+    router.run_inst();
+    assert!(router.get_key_pressed(_w));
+    sender.send(GamepadEvent::Released(GamepadButton::LeftStickUp)).unwrap();
+    router.run_inst();
+    assert!(!router.get_key_pressed(_w));
+
+    router.press_key(_e);
+    sender.send(GamepadEvent::Pressed(GamepadButton::South)).unwrap();
+    router.run_inst();
+    router.release_key(_e);
+    assert!(router.get_key_pressed(_e));
+    sender.send(GamepadEvent::Released(GamepadButton::South)).unwrap();
+    router.run_inst();
+    assert!(!router.get_key_pressed(_e));
+}
+
+// A core that only keeps track of the keys and hotkeys it was given
+#[derive(Default)]
+struct KeyCore {
+    keys: Vec<usize>,
+    hotkeys: Vec<Hotkey>
+}
+
+impl Core for KeyCore {
+    fn get_width(&self) -> usize { 0 }
+    fn get_height(&self) -> usize { 0 }
+    fn set_num_output_channels(&mut self, _value: usize) {}
+    fn set_seconds_per_output_sample(&mut self, _value: f32) {}
+    fn run_inst(&mut self) {}
+    fn run_frame(&mut self) {}
+    fn get_sample_queue_length(&self) -> usize { 0 }
+    fn get_sample(&mut self) -> f32 { 0.0 }
+    fn press_key(&mut self, key_index: usize) { self.keys.push(key_index); }
+    fn release_key(&mut self, key_index: usize) { self.keys.retain(|key| *key != key_index); }
+    fn get_key_pressed(&self, key_index: usize) -> bool { self.keys.contains(&key_index) }
+    fn draw(&self, _frame: &mut [u8]) {}
+}
+
+impl Hotkeys for KeyCore {
+    fn hotkey(&mut self, hotkey: Hotkey) {
+        self.hotkeys.push(hotkey);
+    }
+}
+
+#[test]
+fn test_hotkeys() {
+    // Hotkeys run once per press and never reach the core as keypad keys
+    let mut keymap = HostKeymap::new(KeymapPreset::Qwerty);
+    keymap.bind(Binding::Hotkey(Hotkey::RecordVideo), &[HostKey::Key(K::F9), HostKey::Button(GamepadButton::South)]);
+    let _f9 = keymap.host_keys().iter().position(|key| *key == K::F9).unwrap();
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let mut router = KeyRouter::new(KeyCore::default(), &keymap, receiver);
+    router.press_key(_f9);
+    assert!(router.get_key_pressed(_f9));
+    sender.send(GamepadEvent::Pressed(GamepadButton::South)).unwrap();
+    router.run_frame();
+    router.release_key(_f9);
+    sender.send(GamepadEvent::Released(GamepadButton::South)).unwrap();
+    router.run_frame();
+    router.press_key(_f9);
+    assert_eq!(router.core.hotkeys, vec![Hotkey::RecordVideo, Hotkey::RecordVideo]);
+    assert!(router.core.keys.is_empty());
+}
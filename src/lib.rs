@@ -0,0 +1,6 @@
+// The emulator core, shared by the native and web frontends and the libretro core
+pub mod core;
+
+// The traits the core is driven through, so crates using it don't need their own copies of these dependencies
+pub use basic_emu_frontend::Core;
+pub use clap::ValueEnum;
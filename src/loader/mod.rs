@@ -0,0 +1,147 @@
+// Loading ROMs and the other files given on the command line. ROMs can be plain (.ch8, .sc8, .xo8, .c8x or any other
+// extension) or zipped, and the extension picks a default target. Problems are returned as LoadErrors, which have a
+// message to show and an exit code to stop with (from sysexits.h).
+
+#[cfg(test)]
+mod test;
+
+use crate::core::Target;
+use clap::ValueEnum;
+use std::fmt;
+use std::io::{Cursor, Read};
+use std::path::Path;
+use zip::ZipArchive;
+
+const EXIT_DATA_ERROR: i32 = 65;
+const EXIT_NO_INPUT: i32 = 66;
+// The start of a zip's first local file header
+const ZIP_SIGNATURE: &[u8] = b"PK\x03\x04";
+
+// ROM extensions and the target each one is for
+const ROM_EXTENSIONS: [(&str, Target); 4] = [
+    ("ch8", Target::Chip),
+    ("sc8", Target::SuperModern),
+    ("xo8", Target::XO),
+    ("c8x", Target::Chip8X)
+];
+
+#[derive(Debug)]
+pub enum LoadError {
+    Open(String, std::io::Error),
+    Empty(String),
+    Zip(String, String),
+    TooBig {
+        path: String,
+        size: usize,
+        max: usize,
+        target: Target
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Open(path, error) => write!(f, "couldn't open {}: {}", path, error),
+            LoadError::Empty(path) => write!(f, "{} is empty", path),
+            LoadError::Zip(path, error) => write!(f, "couldn't read the zip {}: {}", path, error),
+            LoadError::TooBig { path, size, max, target } => {
+                let _name = target.to_possible_value().map(|value| value.get_name().to_string()).unwrap_or_default();
+                write!(f, "{} is {} bytes, which is more than the {} bytes that fit in memory for the {} target", path, size, max, _name)
+            }
+        }
+    }
+}
+
+impl LoadError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            LoadError::Open(..) => EXIT_NO_INPUT,
+            _ => EXIT_DATA_ERROR
+        }
+    }
+
+    // Show the error and stop
+    pub fn exit(&self) -> ! {
+        eprintln!("chippy: {}", self);
+        std::process::exit(self.exit_code())
+    }
+}
+
+pub struct Rom {
+    pub data: Vec<u8>,
+    // The target for the ROM's extension, if it has one of the ROM extensions
+    pub target: Option<Target>
+}
+
+impl Rom {
+    // Check that the ROM fits in memory for a target
+    pub fn check_size(&self, path: &str, target: &Target) -> Result<(), LoadError> {
+        let _max = target.max_rom_size();
+        match self.data.len() > _max {
+            true => Err(LoadError::TooBig { path: path.to_string(), size: self.data.len(), max: _max, target: target.clone() }),
+            false => Ok(())
+        }
+    }
+}
+
+pub fn read_file(path: &str) -> Result<Vec<u8>, LoadError> {
+    std::fs::read(path).map_err(|error| LoadError::Open(path.to_string(), error))
+}
+
+// Load a ROM, taking it out of the zip if it's zipped
+pub fn load_rom(path: &str) -> Result<Rom, LoadError> {
+    let _file = read_file(path)?;
+    let (_name, _data) = match extension(path) == "zip" || _file.starts_with(ZIP_SIGNATURE) {
+        true => unzip_rom(&_file).map_err(|error| LoadError::Zip(path.to_string(), error))?,
+        false => (path.to_string(), _file)
+    };
+    if _data.is_empty() {
+        return Err(LoadError::Empty(path.to_string()));
+    }
+    Ok(Rom {
+        data: _data,
+        target: rom_extension_target(&_name)
+    })
+}
+
+fn extension(path: &str) -> String {
+    Path::new(path).extension().map(|extension| extension.to_string_lossy().to_lowercase()).unwrap_or_default()
+}
+
+fn rom_extension_target(path: &str) -> Option<Target> {
+    let _extension = extension(path);
+    ROM_EXTENSIONS.iter().find(|(rom_extension, _target)| *rom_extension == _extension).map(|(_extension, target)| target.clone())
+}
+
+// The largest ROM any target can load
+fn max_rom_size() -> usize {
+    Target::value_variants().iter().map(Target::max_rom_size).max().unwrap_or_default()
+}
+
+// The ROM in a zip and its name. This is the first file with a ROM extension, or the only file if there's just one.
+// Files are only decompressed up to the largest ROM size, so a zip bomb can't use up memory.
+fn unzip_rom(data: &[u8]) -> Result<(String, Vec<u8>), String> {
+    let mut archive = ZipArchive::new(Cursor::new(data)).map_err(|error| error.to_string())?;
+    let _names: Vec<(usize, String)> = (0 .. archive.len())
+        .filter_map(|i| archive.name_for_index(i).map(|name| (i, name.to_string())))
+        .filter(|(_i, name)| !name.ends_with('/'))
+        .collect();
+    let (_index, _name) = match _names.iter().find(|(_i, name)| rom_extension_target(name).is_some()) {
+        Some(entry) => entry,
+        None if _names.len() == 1 => &_names[0],
+        None => return Err(String::from("there's no .ch8, .sc8, .xo8 or .c8x file in it"))
+    };
+    let mut file = archive.by_index(*_index).map_err(|error| format!("couldn't read {}: {}", _name, error))?;
+    let _max = max_rom_size();
+    let _too_big = || format!("{} is more than the {} bytes that fit in memory for any target", _name, _max);
+    if file.size() > _max as u64 {
+        return Err(_too_big());
+    }
+    // The size in the zip can be wrong, so this stops reading past it too
+    let mut rom = Vec::new();
+    file.by_ref().take(_max as u64 + 1).read_to_end(&mut rom).map_err(|error| format!("{} is corrupt, {}", _name, error))?;
+    if rom.len() > _max {
+        return Err(_too_big());
+    }
+    Ok((_name.clone(), rom))
+}
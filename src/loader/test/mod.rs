@@ -0,0 +1,78 @@
+use super::{load_rom, max_rom_size, unzip_rom, LoadError, Rom};
+use crate::core::Target;
+
+// The zips are made with Info-ZIP's zip command
+const CORAX: &[u8] = include_bytes!("../../../roms/3-corax+.ch8");
+// docs/readme.txt and corax+.sc8, deflated (zip -X -r corax.zip docs corax+.sc8)
+const CORAX_ZIP: &[u8] = include_bytes!("corax.zip");
+// The CHIP-8 logo ROM stored with no extension (zip -X -0 logo.zip logo)
+const LOGO_ZIP: &[u8] = include_bytes!("logo.zip");
+// 17 MB of zeros, deflated to about 17 KB (zip -X -9 bomb.zip bomb.ch8)
+const BOMB_ZIP: &[u8] = include_bytes!("bomb.zip");
+
+#[test]
+fn test_load_rom() {
+    let rom = load_rom("roms/3-corax+.ch8").unwrap();
+    assert_eq!(rom.data, CORAX);
+    assert_eq!(rom.target, Some(Target::Chip));
+
+    let rom = load_rom("src/loader/test/corax.zip").unwrap();
+    assert_eq!(rom.data, CORAX);
+    assert_eq!(rom.target, Some(Target::SuperModern));
+
+    let error = load_rom("roms/missing.ch8").err().unwrap();
+    assert!(matches!(error, LoadError::Open(..)));
+    assert_eq!(error.exit_code(), 66);
+}
+
+#[test]
+fn test_rom_size() {
+    let rom = Rom {
+        data: vec![0; 0xE01],
+        target: None
+    };
+    let error = rom.check_size("big.ch8", &Target::Chip).err().unwrap();
+    assert_eq!(error.to_string(), "big.ch8 is 3585 bytes, which is more than the 3584 bytes that fit in memory for the chip target");
+    assert_eq!(error.exit_code(), 65);
+    assert!(rom.check_size("big.ch8", &Target::XO).is_ok());
+    assert_eq!(Target::XO.max_rom_size(), 0xFE00);
+    assert_eq!(Target::Chip8X.max_rom_size(), 0xD00);
+}
+
+#[test]
+fn test_unzip_rom() {
+    let (name, data) = unzip_rom(CORAX_ZIP).unwrap();
+    assert_eq!(name, "corax+.sc8");
+    assert_eq!(data, CORAX);
+
+    // With only one file it doesn't need a ROM extension, the docs/ directory doesn't count as a file
+    let (name, data) = unzip_rom(LOGO_ZIP).unwrap();
+    assert_eq!(name, "logo");
+    assert_eq!(data, include_bytes!("../../../roms/1-chip8-logo.ch8"));
+    let mut readme = CORAX_ZIP.to_vec();
+    let _rom_name = readme.windows(10).rposition(|name| name == b"corax+.sc8").unwrap();
+    readme[_rom_name + 7 .. _rom_name + 10].copy_from_slice(b"txt");
+    assert_eq!(unzip_rom(&readme).err(), Some(String::from("there's no .ch8, .sc8, .xo8 or .c8x file in it")));
+
+    // Damaged files are caught by their checksums if they still decompress
+    let mut damaged = LOGO_ZIP.to_vec();
+    damaged[40] ^= 0xFF;
+    assert_eq!(unzip_rom(&damaged).err(), Some(String::from("logo is corrupt, Invalid checksum")));
+    assert_eq!(unzip_rom(&LOGO_ZIP[.. 100]).err(), Some(String::from("invalid Zip archive: Could not find EOCD")));
+}
+
+#[test]
+fn test_zip_bomb() {
+    assert_eq!(max_rom_size(), 0x1000000 - 0x200);
+    let _error = format!("bomb.ch8 is more than the {} bytes that fit in memory for any target", max_rom_size());
+    assert_eq!(unzip_rom(BOMB_ZIP).err(), Some(_error.clone()));
+
+    // Even if the zip says it's small
+    let mut lying = BOMB_ZIP.to_vec();
+    for signature in [&b"PK\x03\x04"[..], &b"PK\x01\x02"[..]] {
+        let _header = lying.windows(4).position(|bytes| bytes == signature).unwrap();
+        let _size = _header + if signature[2] == 3 { 22 } else { 24 };
+        lying[_size .. _size + 4].copy_from_slice(&0x100_u32.to_le_bytes());
+    }
+    assert_eq!(unzip_rom(&lying).err(), Some(_error));
+}
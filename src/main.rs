@@ -1,7 +1,17 @@
 mod utils;
-mod core;
+mod config;
+mod gamepad;
+mod keys;
+mod loader;
+mod recorder;
+mod romdb;
 
-use basic_emu_frontend::{block_on, keymap::Keymap, Frontend, SyncModes, VirtualKeyCode};
+use basic_emu_frontend::{block_on, keymap::Keymap, Core, Frontend, SyncModes};
+use chippy::core;
+use config::{Config, Profile};
+use keys::{HostKeymap, KeyRouter, KeymapPreset};
+use recorder::Recorder;
+use romdb::RomDatabase;
 use clap::Parser;
 use std::sync::{Arc, Mutex};
 
@@ -12,43 +22,110 @@ struct Args {
     #[arg(short, long)]
     input: String,
 
+    // The path to the config file, instead of ~/.config/chippy/config.toml
+    #[arg(long)]
+    config: Option<String>,
+
+    // The path to a copy of programs.json from the CHIP-8 program database, instead of the bundled one
+    #[arg(long)]
+    rom_db: Option<String>,
+
     // The number of instructions to run per frame
-    #[arg(short, long, default_value_t = 0)]
-    clock: u32,
+    #[arg(short, long)]
+    clock: Option<u32>,
+
+    // The number of instructions to run per second, this overrides the clock
+    #[arg(long)]
+    clock_hz: Option<f32>,
 
     // The platform you are targetting
-    #[arg(short, long, default_value_t, value_enum)]
-    target: core::Target,
+    #[arg(short, long, value_enum)]
+    target: Option<core::Target>,
+
+    // How instructions are counted against each frame, vip charges what each instruction cost on the COSMAC VIP
+    #[arg(long, value_enum)]
+    timing: Option<core::Timing>,
+
+    // The keyboard layout to map the keypad for
+    #[arg(short, long, value_enum)]
+    keymap: Option<KeymapPreset>,
+
+    // The path to a keymap file to change the keymap with. A ROM's own keymap file (its path with a .keymap extension) is applied after it.
+    #[arg(long)]
+    keymap_file: Option<String>,
 
     // The method for syncing the emulation
-    #[arg(short, long, default_value_t, value_enum)]
-    sync: SyncModes
-}
+    #[arg(short, long, value_enum)]
+    sync: Option<SyncModes>,
+
+    // Display persistence, to reduce sprite flicker
+    #[arg(short, long, value_enum)]
+    persistence: Option<core::Persistence>,
+
+    // The colours to use for XO-CHIP's bit planes
+    #[arg(long, value_enum)]
+    palette: Option<core::Palette>,
+
+    // Audio volume, 1 is the default level
+    #[arg(long)]
+    volume: Option<f32>,
+
+    // Cutoff in Hz of a low-pass filter for the audio, to soften the buzzer (0 disables it)
+    #[arg(long)]
+    low_pass: Option<f32>,
+
+    // The number of frames to blend together with the blend persistence mode
+    #[arg(long)]
+    blend_frames: Option<usize>,
+
+    // Override the target's quirk for counting collided/clipped sprite rows in VF in high-res mode
+    #[arg(long)]
+    collision_rows: Option<bool>,
+
+    // Override the target's quirk for using four bit planes (16 colours) in XO-CHIP
+    #[arg(long)]
+    four_planes: Option<bool>,
+
+    // Override the target's quirk for running frames and timers at 50 Hz (on for eti-660 and dream-6800)
+    #[arg(long)]
+    pal_timing: Option<bool>,
+
+    // The path to the CHIP-8 interpreter machine code (0000-01FF) for the cosmac-vip target
+    #[arg(long)]
+    vip_interpreter: Option<String>,
+
+    // The font set to use instead of the target's own font
+    #[arg(long, value_enum)]
+    font: Option<core::FontSet>,
 
-// Keymap (Assumes QWERTY for now)
-//     QWERTY:        CHIP-8;
-//     1 2 3 4        1 2 3 C
-//     Q W E R   ->   4 5 6 D
-//     A S D F        7 8 9 E
-//     Z X C V        A 0 B F
-const KEYMAP: [VirtualKeyCode; 16] = [
-    VirtualKeyCode::X,
-    VirtualKeyCode::Key1,
-    VirtualKeyCode::Key2,
-    VirtualKeyCode::Key3,
-    VirtualKeyCode::Q,
-    VirtualKeyCode::W,
-    VirtualKeyCode::E,
-    VirtualKeyCode::A,
-    VirtualKeyCode::S,
-    VirtualKeyCode::D,
-    VirtualKeyCode::Z,
-    VirtualKeyCode::C,
-    VirtualKeyCode::Key4,
-    VirtualKeyCode::R,
-    VirtualKeyCode::F,
-    VirtualKeyCode::V
-];
+    // The path to a custom font, 80 bytes of small font optionally followed by 160 bytes of big font
+    #[arg(long)]
+    font_file: Option<String>,
+
+    // The path to write the audio output to as a WAV file
+    #[arg(long)]
+    record_audio: Option<String>,
+
+    // The path to record the display to, or - for stdout. F9 also starts and stops recording, to chippy-N.gif/raw
+    #[arg(long)]
+    record_video: Option<String>,
+
+    // The format for video recordings, raw is RGBA frames at the display's size for piping to an encoder
+    #[arg(long, default_value_t, value_enum)]
+    video_format: core::VideoFormat,
+
+    // Run without a window or audio device, for the number of frames given by --frames
+    #[arg(long)]
+    headless: bool,
+
+    // The number of frames to run in headless mode
+    #[arg(long, default_value_t = 600)]
+    frames: u32,
+
+    // The audio sample rate in headless mode
+    #[arg(long, default_value_t = 44100)]
+    sample_rate: u32
+}
 
 fn main() {
     // In the browser, create and run the core/frontend from the JS side.
@@ -57,28 +134,95 @@ fn main() {
 }
 
 async fn run() {
+    let _args = Args::parse();
+    let _rom = loader::load_rom(&_args.input).unwrap_or_else(|error| error.exit());
+    // Command line arguments override the config's profiles for the ROM, which override what the ROM database has
+    // for it, which overrides the target for the ROM's extension, which overrides the config's defaults
+    let _program = RomDatabase::load(_args.rom_db.as_deref()).unwrap_or_else(|error| error.exit()).lookup(&_rom.data);
+    let mut _known = Profile { target: _rom.target.clone(), ..Profile::default() };
+    if let Some(program) = &_program {
+        _known.merge(&program.profile);
+    }
+    let _config = Config::load(_args.config.as_deref()).unwrap_or_else(|error| error.exit());
+    let mut _profile = _config.profile(&_args.input, &_rom.data, &_known);
+    if let Some(program) = &_program {
+        eprintln!("{}", program.describe());
+    }
     let core = {
-        let _args = Args::parse();
-        let _rom = utils::load_rom(&_args.input);
-        let mut clock = _args.clock;
-        if clock == 0 {
-            clock = match _args.target {
-                core::Target::Chip => 11,
-                core::Target::SuperModern => 30,
-                core::Target::SuperLegacy => 30,
-                core::Target::XO => 1000
-            }
+        let _target = _args.target.clone().or(_profile.target.clone()).unwrap_or_default();
+        let _clock = _args.clock.or(_profile.clock).filter(|clock| *clock != 0).unwrap_or(_target.default_clock());
+        // A clock from the command line beats a clock in Hz from the config
+        let _clock_hz = _args.clock_hz.or(_profile.clock_hz.filter(|_hz| _args.clock.is_none()));
+        let mut quirks = core::Quirks::for_target(&_target);
+        _profile.apply_quirks(&mut quirks);
+        if let Some(collision_rows) = _args.collision_rows {
+            quirks.collision_rows = collision_rows;
+        }
+        if let Some(four_planes) = _args.four_planes {
+            quirks.four_planes = four_planes;
+        }
+        if let Some(pal_timing) = _args.pal_timing {
+            quirks.pal_timing = pal_timing;
         }
-        core::Chip8::new(_args.target, clock, _rom)
+        _rom.check_size(&_args.input, &_target).unwrap_or_else(|error| error.exit());
+        let mut chip8 = core::Chip8::new(_target, _clock, _rom.data);
+        chip8.set_quirks(quirks);
+        if let Some(hz) = _clock_hz {
+            chip8.set_clock_hz(hz);
+        }
+        chip8.set_timing(_args.timing.or(_profile.timing).unwrap_or_default());
+        // The frontend calls run_frame at the monitor's refresh rate with vsync, headless mode runs as fast as it can
+        chip8.set_host_pacing(!_args.headless);
+        chip8.set_persistence(_args.persistence.or(_profile.persistence).unwrap_or_default());
+        chip8.set_blend_frames(_args.blend_frames.or(_profile.blend_frames).unwrap_or(2));
+        if let Some(palette) = _args.palette.map(|palette| palette.colors()).or(_profile.palette) {
+            chip8.set_palette(palette);
+        }
+        chip8.set_volume(_args.volume.or(_profile.volume).unwrap_or(1.0));
+        chip8.set_low_pass(_args.low_pass.or(_profile.low_pass).unwrap_or(0.0));
+        if let Some(path) = &_args.vip_interpreter {
+            chip8.set_vip_interpreter(loader::read_file(path).unwrap_or_else(|error| error.exit()));
+        }
+        if let Some(font) = _args.font {
+            chip8.set_font(&font.bytes());
+        }
+        if let Some(path) = &_args.font_file {
+            chip8.set_font(&loader::read_file(path).unwrap_or_else(|error| error.exit()));
+        }
+        Recorder::new(chip8, _args.record_audio.clone(), _args.record_video.clone(), _args.video_format)
+            .unwrap_or_else(|error| error.exit())
     };
 
-    let sync_mode = Args::parse().sync;
+    if _args.headless {
+        run_headless(core, &_args);
+        return;
+    }
+
+    let mut keymap = HostKeymap::new(_args.keymap.or(_profile.keymap).unwrap_or_default());
+    if let Some(path) = _args.keymap_file.as_ref().or(_profile.keymap_file.as_ref()) {
+        keymap.load_file(path).unwrap_or_else(|error| error.exit());
+    }
+    _profile.apply_keys(&mut keymap);
+    keymap.load_rom_file(&_args.input).unwrap_or_else(|error| error.exit());
 
     let frontend = Frontend::new(
-        Arc::new(Mutex::new(core)),
-        Keymap::new(&KEYMAP),
-        sync_mode
+        Arc::new(Mutex::new(KeyRouter::new(core, &keymap, gamepad::connect()))),
+        Keymap::new(&keymap.host_keys()),
+        _args.sync.or(_profile.sync).unwrap_or_default()
     );
 
     frontend.start().await
 }
+
+// Run a fixed number of frames with no window or audio device, generating samples at a fixed rate
+fn run_headless(mut core: Recorder, args: &Args) {
+    core.set_num_output_channels(1);
+    core.set_seconds_per_output_sample(1.0 / args.sample_rate as f32);
+    for _i in 0 .. args.frames {
+        core.run_frame();
+        while core.get_sample_queue_length() > 0 {
+            core.get_sample();
+        }
+    }
+    core.finish().unwrap_or_else(|error| error.exit());
+}
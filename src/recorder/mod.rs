@@ -0,0 +1,255 @@
+// Wraps the core to write what it outputs to files while the frontend (or the headless loop) runs it,
+// and to report problems the core ran into on stderr. Problems with the recordings are returned as RecordErrors,
+// which stop chippy with an exit code from sysexits.h like LoadErrors do.
+
+#[cfg(test)]
+mod test;
+
+use crate::core::{wav::WavWriter, Chip8, MachineCodeError, VideoFormat};
+use crate::keys::{Hotkey, Hotkeys};
+use basic_emu_frontend::Core;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+const EXIT_CANT_CREATE: i32 = 73;
+const EXIT_IO_ERROR: i32 = 74;
+
+// Write the recorded samples out once this many have built up, or at the end of a frame
+const FLUSH_SAMPLES: usize = 4096;
+
+#[derive(Debug)]
+pub enum RecordError {
+    Create(String, std::io::Error),
+    Write(String, std::io::Error)
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecordError::Create(path, error) => write!(f, "couldn't create {}: {}", path, error),
+            RecordError::Write(path, error) => write!(f, "couldn't write to {}: {}", path, error)
+        }
+    }
+}
+
+impl RecordError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            RecordError::Create(..) => EXIT_CANT_CREATE,
+            RecordError::Write(..) => EXIT_IO_ERROR
+        }
+    }
+
+    // Show the error and stop
+    pub fn exit(&self) -> ! {
+        eprintln!("chippy: {}", self);
+        std::process::exit(self.exit_code())
+    }
+}
+
+pub struct Recorder {
+    chip8: Chip8,
+    audio_path: Option<String>,
+    audio_samples: Vec<f32>,
+    // The file is created up front so problems show up before anything runs, but the WAV header is written on the first
+    // write, since the frontend sets up the output format after the core is created
+    audio_file: Option<File>,
+    audio: Option<WavWriter<BufWriter<File>>>,
+    video_format: VideoFormat,
+    video_path: String,
+    video: Option<BufWriter<Box<dyn Write + Send>>>,
+    // The last machine code problem reported, so a program calling the same routine every frame only reports it once
+    machine_code_error: Option<MachineCodeError>
+}
+
+impl Recorder {
+    pub fn new(mut chip8: Chip8, audio_path: Option<String>, video_path: Option<String>, video_format: VideoFormat) -> Result<Recorder, RecordError> {
+        let mut audio_file = None;
+        if let Some(path) = &audio_path {
+            audio_file = Some(File::create(path).map_err(|error| RecordError::Create(path.clone(), error))?);
+            chip8.start_audio_recording();
+        }
+        let mut recorder = Recorder {
+            chip8,
+            audio_path,
+            audio_samples: Vec::new(),
+            audio_file,
+            audio: None,
+            video_format,
+            video_path: String::new(),
+            video: None,
+            machine_code_error: None
+        };
+        if let Some(path) = video_path {
+            recorder.start_video(&path)?;
+        }
+        Ok(recorder)
+    }
+
+    // Write out everything that's left, for when emulation is over
+    pub fn finish(&mut self) -> Result<(), RecordError> {
+        self.record_audio(true)?;
+        if let (Some(audio), Some(path)) = (&mut self.audio, &self.audio_path) {
+            audio.finish().map_err(|error| RecordError::Write(path.clone(), error))?;
+        }
+        self.stop_video()
+    }
+
+    // Stop on a problem with the recordings, finishing whatever can still be finished first
+    fn check(&mut self, result: Result<(), RecordError>) {
+        if let Err(error) = result {
+            let _ = self.finish();
+            error.exit();
+        }
+    }
+
+    fn report_errors(&mut self) {
+        if let Some(error) = self.chip8.take_machine_code_error() {
+            if self.machine_code_error != Some(error) {
+                eprintln!("chippy: {error}");
+            }
+            self.machine_code_error = Some(error);
+        }
+    }
+
+    // Start recording video to a file, or to stdout if the path is -
+    fn start_video(&mut self, path: &str) -> Result<(), RecordError> {
+        let _output: Box<dyn Write + Send> = if path == "-" {
+            Box::new(std::io::stdout())
+        } else {
+            Box::new(File::create(path).map_err(|error| RecordError::Create(path.to_string(), error))?)
+        };
+        self.video_path = path.to_string();
+        self.video = Some(BufWriter::new(_output));
+        self.chip8.start_video_recording(self.video_format);
+        Ok(())
+    }
+
+    fn stop_video(&mut self) -> Result<(), RecordError> {
+        let _bytes = self.chip8.stop_video_recording();
+        if let Some(mut video) = self.video.take() {
+            video.write_all(&_bytes).and_then(|_| video.flush()).map_err(|error| RecordError::Write(self.video_path.clone(), error))?;
+        }
+        Ok(())
+    }
+
+    // Start or stop recording from the hotkey, recordings go to the first unused chippy-N file
+    fn toggle_video(&mut self) -> Result<(), RecordError> {
+        if self.video.is_some() {
+            return self.stop_video();
+        }
+        let _extension = match self.video_format {
+            VideoFormat::Gif => "gif",
+            VideoFormat::Raw => "raw"
+        };
+        let _path = (1 ..).map(|n| format!("chippy-{n}.{_extension}")).find(|path| !Path::new(path).exists()).unwrap();
+        self.start_video(&_path)
+    }
+
+    fn record_video(&mut self) -> Result<(), RecordError> {
+        let Some(video) = &mut self.video else {
+            return Ok(());
+        };
+        let _bytes = self.chip8.take_video_recording();
+        video.write_all(&_bytes).map_err(|error| RecordError::Write(self.video_path.clone(), error))
+    }
+
+    // Collect the samples recorded by the core, and write them out if enough have built up or flush is set
+    fn record_audio(&mut self, flush: bool) -> Result<(), RecordError> {
+        let Some(path) = &self.audio_path else {
+            return Ok(());
+        };
+        self.audio_samples.append(&mut self.chip8.take_audio_recording());
+        if self.audio_samples.is_empty() || (!flush && self.audio_samples.len() < FLUSH_SAMPLES) {
+            return Ok(());
+        }
+        let _write_error = |error| RecordError::Write(path.clone(), error);
+        if let Some(file) = self.audio_file.take() {
+            let _wav = WavWriter::new(BufWriter::new(file), self.chip8.get_num_output_channels(), self.chip8.get_sample_rate());
+            self.audio = Some(_wav.map_err(_write_error)?);
+        }
+        if let Some(audio) = &mut self.audio {
+            audio.write_samples(&self.audio_samples).map_err(_write_error)?;
+        }
+        self.audio_samples.clear();
+        Ok(())
+    }
+}
+
+impl Core for Recorder {
+    fn get_width(&self) -> usize {
+        self.chip8.get_width()
+    }
+
+    fn get_height(&self) -> usize {
+        self.chip8.get_height()
+    }
+
+    fn set_num_output_channels(&mut self, value: usize) {
+        self.chip8.set_num_output_channels(value);
+    }
+
+    fn set_seconds_per_output_sample(&mut self, value: f32) {
+        self.chip8.set_seconds_per_output_sample(value);
+    }
+
+    fn run_inst(&mut self) {
+        self.chip8.run_inst();
+        let _result = self.record_audio(false).and_then(|_| self.record_video());
+        self.check(_result);
+        self.report_errors();
+    }
+
+    fn run_frame(&mut self) {
+        self.chip8.run_frame();
+        let _result = self.record_audio(true).and_then(|_| self.record_video());
+        self.check(_result);
+        self.report_errors();
+    }
+
+    fn get_sample_queue_length(&self) -> usize {
+        self.chip8.get_sample_queue_length()
+    }
+
+    fn get_sample(&mut self) -> f32 {
+        self.chip8.get_sample()
+    }
+
+    fn press_key(&mut self, key_index: usize) {
+        self.chip8.press_key(key_index);
+    }
+
+    fn release_key(&mut self, key_index: usize) {
+        self.chip8.release_key(key_index);
+    }
+
+    fn get_key_pressed(&self, key_index: usize) -> bool {
+        self.chip8.get_key_pressed(key_index)
+    }
+
+    fn draw(&self, frame: &mut [u8]) {
+        self.chip8.draw(frame);
+    }
+}
+
+// The frontend drops the core when its window closes, so the recordings are finished then as well
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        if let Err(error) = self.finish() {
+            eprintln!("chippy: {}", error);
+        }
+    }
+}
+
+impl Hotkeys for Recorder {
+    fn hotkey(&mut self, hotkey: Hotkey) {
+        match hotkey {
+            Hotkey::RecordVideo => {
+                let _result = self.toggle_video();
+                self.check(_result);
+            }
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use super::{RecordError, Recorder};
+use crate::core::{Chip8, Target, VideoFormat};
+use basic_emu_frontend::Core;
+
+// Start the buzzer with a long sound timer and loop forever
+const BUZZER: [u8; 6] = [0x60, 0xFF, 0xF0, 0x18, 0x12, 0x04];
+
+#[test]
+fn test_recorder() {
+    let _dir = std::env::temp_dir().join(format!("chippy-recorder-{}", std::process::id()));
+    std::fs::create_dir_all(&_dir).unwrap();
+    let _audio_path = _dir.join("out.wav").to_string_lossy().into_owned();
+    let _video_path = _dir.join("out.raw").to_string_lossy().into_owned();
+
+    let chip8 = Chip8::new(Target::Chip, 11, BUZZER.to_vec());
+    let mut recorder = Recorder::new(chip8, Some(_audio_path.clone()), Some(_video_path.clone()), VideoFormat::Raw).unwrap();
+    recorder.set_num_output_channels(1);
+    recorder.set_seconds_per_output_sample(1.0 / 8000.0);
+    for _i in 0 .. 10 {
+        recorder.run_frame();
+    }
+    recorder.finish().unwrap();
+
+    // The sizes in the WAV header match the samples written
+    let _wav = std::fs::read(&_audio_path).unwrap();
+    let _u32_at = |pos: usize| u32::from_le_bytes(_wav[pos .. pos + 4].try_into().unwrap());
+    assert_eq!(_u32_at(4) as usize, _wav.len() - 8);
+    assert_eq!(_u32_at(54) as usize, _wav.len() - 58);
+    assert!(_wav.len() > 58);
+    let _video = std::fs::read(&_video_path).unwrap();
+    assert_eq!(_video.len(), 10 * 128 * 64 * 4);
+    std::fs::remove_dir_all(&_dir).unwrap();
+}
+
+#[test]
+fn test_recorder_drop() {
+    // Recordings are finished when the recorder is dropped without calling finish, like when the window is closed
+    let _dir = std::env::temp_dir().join(format!("chippy-recorder-drop-{}", std::process::id()));
+    std::fs::create_dir_all(&_dir).unwrap();
+    let _audio_path = _dir.join("out.wav").to_string_lossy().into_owned();
+    let _video_path = _dir.join("out.gif").to_string_lossy().into_owned();
+
+    let chip8 = Chip8::new(Target::Chip, 11, BUZZER.to_vec());
+    let mut recorder = Recorder::new(chip8, Some(_audio_path.clone()), Some(_video_path.clone()), VideoFormat::Gif).unwrap();
+    recorder.set_num_output_channels(1);
+    recorder.set_seconds_per_output_sample(1.0 / 8000.0);
+    for _i in 0 .. 10 {
+        recorder.run_frame();
+    }
+    drop(recorder);
+
+    let _wav = std::fs::read(&_audio_path).unwrap();
+    let _u32_at = |pos: usize| u32::from_le_bytes(_wav[pos .. pos + 4].try_into().unwrap());
+    assert_eq!(_u32_at(54) as usize, _wav.len() - 58);
+    assert!(_wav.len() > 58);
+    // The GIF has its trailer, and the one frame held back waiting for the display to change
+    let _gif = std::fs::read(&_video_path).unwrap();
+    assert_eq!(_gif.last(), Some(&0x3B));
+    let mut decoder = gif::DecodeOptions::new().read_info(&_gif[..]).unwrap();
+    assert!(decoder.read_next_frame().unwrap().is_some());
+    assert!(decoder.read_next_frame().unwrap().is_none());
+    std::fs::remove_dir_all(&_dir).unwrap();
+}
+
+#[test]
+fn test_record_errors() {
+    // Files that can't be created are reported before anything runs
+    let chip8 = Chip8::new(Target::Chip, 11, BUZZER.to_vec());
+    let error = Recorder::new(chip8, Some("missing/out.wav".to_string()), None, VideoFormat::Gif).err().unwrap();
+    assert!(matches!(error, RecordError::Create(..)));
+    assert_eq!(error.exit_code(), 73);
+    assert!(error.to_string().starts_with("couldn't create missing/out.wav: "));
+
+    let chip8 = Chip8::new(Target::Chip, 11, BUZZER.to_vec());
+    let error = Recorder::new(chip8, None, Some("missing/out.gif".to_string()), VideoFormat::Gif).err().unwrap();
+    assert!(matches!(error, RecordError::Create(..)));
+}
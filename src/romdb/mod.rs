@@ -0,0 +1,173 @@
+// Identifying ROMs by their SHA-1 with the CHIP-8 community program database (https://github.com/chip-8/chip-8-database).
+// The database's programs.json lists each program's ROMs by SHA-1, with the platforms they run on, the tickrate and
+// optionally quirks, colours and keys, which are turned into a profile with the lowest priority (the config and command
+// line override it). A copy of programs.json is bundled, and another one can be used with --rom-db.
+
+#[cfg(test)]
+mod test;
+
+use crate::config::{self, Profile};
+use crate::core::{Palette, Target};
+use crate::gamepad::GamepadButton;
+use crate::utils;
+use serde_json::Value;
+use std::fmt;
+
+const BUNDLED_PROGRAMS: &str = include_str!("programs.json");
+
+const EXIT_DATA_ERROR: i32 = 65;
+const EXIT_NO_INPUT: i32 = 66;
+
+#[derive(Debug)]
+pub enum RomDatabaseError {
+    Open(String, std::io::Error),
+    Parse(String, String)
+}
+
+impl fmt::Display for RomDatabaseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RomDatabaseError::Open(path, error) => write!(f, "couldn't open the ROM database {}: {}", path, error),
+            RomDatabaseError::Parse(path, error) => write!(f, "couldn't read the ROM database {}: {}", path, error)
+        }
+    }
+}
+
+impl RomDatabaseError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            RomDatabaseError::Open(..) => EXIT_NO_INPUT,
+            RomDatabaseError::Parse(..) => EXIT_DATA_ERROR
+        }
+    }
+
+    // Show the error and stop
+    pub fn exit(&self) -> ! {
+        eprintln!("chippy: {}", self);
+        std::process::exit(self.exit_code())
+    }
+}
+
+// What the database knows about a ROM
+pub struct Program {
+    pub title: String,
+    pub authors: Vec<String>,
+    pub release: Option<String>,
+    pub profile: Profile
+}
+
+impl Program {
+    // The title, release year and authors, e.g. "Pong (1990) by Paul Vervalin"
+    pub fn describe(&self) -> String {
+        let mut description = self.title.clone();
+        if let Some(release) = &self.release {
+            description += &format!(" ({})", release);
+        }
+        if !self.authors.is_empty() {
+            description += &format!(" by {}", self.authors.join(", "));
+        }
+        description
+    }
+}
+
+pub struct RomDatabase {
+    programs: Vec<Value>
+}
+
+impl RomDatabase {
+    // Load the database from a copy of programs.json, or use the bundled one
+    pub fn load(path: Option<&str>) -> Result<RomDatabase, RomDatabaseError> {
+        let Some(path) = path else {
+            return Ok(RomDatabase::parse(BUNDLED_PROGRAMS).expect("the bundled ROM database should be valid"));
+        };
+        let _text = std::fs::read_to_string(path).map_err(|error| RomDatabaseError::Open(path.to_string(), error))?;
+        RomDatabase::parse(&_text).map_err(|error| RomDatabaseError::Parse(path.to_string(), error))
+    }
+
+    pub fn parse(text: &str) -> Result<RomDatabase, String> {
+        match serde_json::from_str(text).map_err(|error| error.to_string())? {
+            Value::Array(programs) => Ok(RomDatabase { programs }),
+            _ => Err(String::from("expected a list of programs"))
+        }
+    }
+
+    pub fn lookup(&self, rom: &[u8]) -> Option<Program> {
+        let _hash = utils::sha1_hex(rom);
+        self.programs.iter().find_map(|program| {
+            let _rom = program["roms"].get(&_hash)?;
+            Some(Program {
+                title: program["title"].as_str().unwrap_or("Unknown program").to_string(),
+                authors: program["authors"].as_array().into_iter().flatten().filter_map(|author| author.as_str().map(str::to_string)).collect(),
+                release: program["release"].as_str().map(str::to_string),
+                profile: rom_profile(_rom)
+            })
+        })
+    }
+}
+
+// The target for a platform in the database, if there's one that runs it
+fn platform_target(platform: &str) -> Option<Target> {
+    match platform {
+        "originalChip8" | "hybridVIP" | "modernChip8" => Some(Target::Chip),
+        "chip8x" => Some(Target::Chip8X),
+        "chip48" => Some(Target::Chip48),
+        "superchip1" => Some(Target::Super10),
+        "superchip" => Some(Target::SuperLegacy),
+        "megachip8" => Some(Target::MegaChip),
+        "xochip" => Some(Target::XO),
+        _ => None
+    }
+}
+
+// The database's quirks by their names in the config, memoryLeaveIUnchanged is the opposite of memory-increment
+fn quirk_name(quirk: &str, value: bool) -> Option<(&'static str, bool)> {
+    match quirk {
+        "shift" => Some(("shift", value)),
+        "memoryIncrementByX" => Some(("memory-increment-by-x", value)),
+        "memoryLeaveIUnchanged" => Some(("memory-increment", !value)),
+        "wrap" => Some(("wrap", value)),
+        "jump" => Some(("jump", value)),
+        "vblank" => Some(("display-wait", value)),
+        "logic" => Some(("vf-reset", value)),
+        _ => None
+    }
+}
+
+// Game controls in the database, and the buttons to bind to them
+const KEY_BUTTONS: [(&str, &[GamepadButton]); 6] = [
+    ("up", &[GamepadButton::DPadUp, GamepadButton::LeftStickUp]),
+    ("down", &[GamepadButton::DPadDown, GamepadButton::LeftStickDown]),
+    ("left", &[GamepadButton::DPadLeft, GamepadButton::LeftStickLeft]),
+    ("right", &[GamepadButton::DPadRight, GamepadButton::LeftStickRight]),
+    ("a", &[GamepadButton::South]),
+    ("b", &[GamepadButton::East])
+];
+
+fn rom_profile(rom: &Value) -> Profile {
+    let mut profile = Profile::default();
+    // Platforms are listed from the most to least suitable
+    let _platform = rom["platforms"].as_array().into_iter().flatten().filter_map(Value::as_str).find(|platform| platform_target(platform).is_some());
+    if let Some(platform) = _platform {
+        profile.target = platform_target(platform);
+        if let Some(quirks) = rom["quirkyPlatforms"][platform].as_object() {
+            let _quirks = quirks.iter().filter_map(|(quirk, value)| quirk_name(quirk, value.as_bool()?));
+            profile.quirks = _quirks.map(|(name, value)| (name.to_string(), value)).collect();
+        }
+    }
+    profile.clock = rom["tickrate"].as_u64().map(|tickrate| tickrate as u32);
+    if let Some(pixels) = rom["colors"]["pixels"].as_array() {
+        let mut palette = Palette::default().colors();
+        for (i, color) in pixels.iter().take(palette.len()).enumerate() {
+            if let Some(color) = color.as_str().and_then(config::parse_color) {
+                palette[i] = color;
+            }
+        }
+        profile.palette = Some(palette);
+    }
+    for (name, buttons) in KEY_BUTTONS {
+        if let Some(index) = rom["keys"][name].as_u64().filter(|index| *index < 0x10) {
+            profile.buttons.extend(buttons.iter().map(|button| (*button, index as usize)));
+        }
+    }
+    profile
+}
@@ -0,0 +1,78 @@
+use super::{RomDatabase, RomDatabaseError};
+use crate::core::{Palette, Quirks, Target};
+use crate::gamepad::GamepadButton;
+use crate::keys::{Binding, HostKeymap, KeymapPreset};
+
+const PROGRAMS: &str = r##"[
+    {
+        "title": "Paddles",
+        "authors": ["Someone", "Someone Else"],
+        "roms": {
+            "a9993e364706816aba3e25717850c26c9cd0d89d": {
+                "file": "paddles.ch8",
+                "platforms": ["chip8e", "superchip", "xochip"],
+                "tickrate": 20,
+                "quirkyPlatforms": {
+                    "superchip": { "memoryLeaveIUnchanged": false, "vblank": true }
+                },
+                "colors": { "pixels": ["#102030", "#ffffff"], "buzzer": "#ff0000" },
+                "keys": { "up": 1, "down": 4, "a": 10 }
+            }
+        }
+    }
+]"##;
+
+#[test]
+fn test_rom_database() {
+    let database = RomDatabase::parse(PROGRAMS).unwrap();
+    assert!(database.lookup(b"abd").is_none());
+    let program = database.lookup(b"abc").unwrap();
+    assert_eq!(program.describe(), "Paddles by Someone, Someone Else");
+
+    // chip8e isn't supported, so it uses the next platform
+    let profile = program.profile;
+    assert_eq!(profile.target, Some(Target::SuperLegacy));
+    assert_eq!(profile.clock, Some(20));
+    let mut quirks = Quirks::for_target(&Target::SuperLegacy);
+    profile.apply_quirks(&mut quirks);
+    assert!(quirks.memory_increment);
+    assert!(quirks.display_wait);
+    let mut palette = Palette::default().colors();
+    palette[0] = [0x10, 0x20, 0x30, 0xff];
+    palette[1] = [0xff, 0xff, 0xff, 0xff];
+    assert_eq!(profile.palette, Some(palette));
+
+    // The controls go on the gamepad, the keyboard is left alone
+    let mut keymap = HostKeymap::new(KeymapPreset::Qwerty);
+    let _keys = keymap.host_keys();
+    profile.apply_keys(&mut keymap);
+    assert_eq!(keymap.host_keys(), _keys);
+    let _buttons = keymap.buttons();
+    assert!(_buttons.contains(&(GamepadButton::DPadUp, Binding::Keypad(0x1))));
+    assert!(_buttons.contains(&(GamepadButton::LeftStickDown, Binding::Keypad(0x4))));
+    assert!(_buttons.contains(&(GamepadButton::South, Binding::Keypad(0xA))));
+    assert!(!_buttons.contains(&(GamepadButton::DPadUp, Binding::Keypad(0x5))));
+    assert!(_buttons.contains(&(GamepadButton::East, Binding::Keypad(0x4))));
+
+    assert_eq!(RomDatabase::parse("{}").err(), Some(String::from("expected a list of programs")));
+}
+
+#[test]
+fn test_bundled_rom_database() {
+    let database = RomDatabase::load(None).unwrap();
+    let program = database.lookup(&std::fs::read("roms/8-scrolling.ch8").unwrap()).unwrap();
+    assert!(program.describe().contains("Timendus"));
+    assert!(program.profile.target.is_some());
+    assert!(database.lookup(&std::fs::read("roms/xo-planes.ch8").unwrap()).is_none());
+}
+
+#[test]
+fn test_rom_database_errors() {
+    let error = RomDatabase::load(Some("roms/missing.json")).err().unwrap();
+    assert!(matches!(error, RomDatabaseError::Open(..)));
+    assert_eq!(error.exit_code(), 66);
+    let error = RomDatabase::load(Some("src/main.rs")).err().unwrap();
+    assert!(matches!(error, RomDatabaseError::Parse(..)));
+    assert_eq!(error.exit_code(), 65);
+    assert!(error.to_string().starts_with("couldn't read the ROM database src/main.rs"));
+}
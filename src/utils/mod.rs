@@ -1,6 +1,6 @@
-pub fn load_rom(path: &str) -> Vec<u8> {
-    match std::fs::read(path) {
-        Ok(file) => file,
-        Err(error) => panic!("Problem opening the ROM: {error:?}")
-    }
+use sha1::{Digest, Sha1};
+
+// The SHA-1 of some data as lowercase hex, which is how ROMs are identified in configs and the program database
+pub fn sha1_hex(data: &[u8]) -> String {
+    Sha1::digest(data).iter().map(|byte| format!("{:02x}", byte)).collect()
 }
@@ -0,0 +1,73 @@
+// Prints the expected XO-CHIP planes for the xo-planes test ROMs, as used in src/core/test/data/mod.rs.
+// The sprite drawing follows Octo's emulator.js (pixel by pixel, wrapping around the edges, with each selected plane
+// taking its own sprite data from I), so the expected output doesn't come from chippy's own bitwise drawing.
+//
+// Usage: node tools/octo-planes.js roms/xo-planes.ch8
+
+const fs = require('fs');
+
+function run(rom) {
+	const m = new Uint8Array(0x10000);
+	m.set(rom, 0x200);
+	const v = new Uint8Array(16);
+	const p = [new Uint8Array(128 * 64), new Uint8Array(128 * 64)];
+	let pc = 0x200, i = 0, plane = 1, hires = false;
+
+	function sprite(x, y, len) {
+		const rowSize = hires ? 128 : 64;
+		const colSize = hires ? 64 : 32;
+		const width = len == 0 ? 16 : 8;
+		const height = len == 0 ? 16 : len;
+		let addr = i;
+		v[0xF] = 0;
+		for (let layer = 0; layer < 2; layer++) {
+			if ((plane & (layer + 1)) == 0) { continue; }
+			for (let a = 0; a < height; a++) {
+				for (let b = 0; b < width; b++) {
+					const target = ((x + b) % rowSize) + ((y + a) % colSize) * rowSize;
+					const byte = m[addr + a * (width / 8) + (b > 7 ? 1 : 0)];
+					if (((byte >> (7 - (b % 8))) & 1) == 0) { continue; }
+					if (p[layer][target]) { p[layer][target] = 0; v[0xF] = 1; }
+					else { p[layer][target] = 1; }
+				}
+			}
+			addr += height * (width / 8);
+		}
+	}
+
+	// Only the instructions the test ROMs use, stopping at the jump to itself they end with
+	for (;;) {
+		const op = (m[pc] << 8) | m[pc + 1];
+		const x = (op >> 8) & 0xF, y = (op >> 4) & 0xF, nn = op & 0xFF, nnn = op & 0xFFF;
+		pc += 2;
+		if (op == 0x00FF) { hires = true; }
+		else if ((op & 0xF0FF) == 0xF001) { plane = x; }
+		else if ((op & 0xF000) == 0x1000) { if (nnn == pc - 2) { break; } pc = nnn; }
+		else if ((op & 0xF000) == 0x6000) { v[x] = nn; }
+		else if ((op & 0xF00F) == 0x8000) { v[x] = v[y]; }
+		else if ((op & 0xF000) == 0xA000) { i = nnn; }
+		else if ((op & 0xF000) == 0xD000) { sprite(v[x], v[y], op & 0xF); }
+		else { throw new Error('Unsupported instruction ' + op.toString(16)); }
+	}
+	return { p, hires };
+}
+
+// chippy keeps each row of a plane in a u128 with the leftmost pixel in the top bit, and low-res pixels in the top left quarter
+function printPlane(name, pixels, hires) {
+	const rowSize = hires ? 128 : 64;
+	console.log(`pub const ${name}: [u128; core::HEIGHT] = [`);
+	for (let y = 0; y < 64; y++) {
+		let row = '';
+		for (let x = 0; x < 128; x++) {
+			const inside = x < rowSize && y < (hires ? 64 : 32);
+			row += inside && pixels[x + y * rowSize] ? '1' : '0';
+		}
+		console.log(`    0b${row},`);
+	}
+	console.log('];');
+}
+
+const prefix = process.argv[3] || 'XO_PLANES';
+const { p, hires } = run(fs.readFileSync(process.argv[2]));
+printPlane(`${prefix}_PLANE_0`, p[0], hires);
+printPlane(`${prefix}_PLANE_1`, p[1], hires);
@@ -1,6 +1,7 @@
 import {
     JsApi,
     Keymap,
+    VideoFormat,
     press_key,
     release_key,
 } from '../wasm/chippy';
@@ -25,7 +26,12 @@ const romFileInput = document.querySelector('#rom-file-input') as HTMLInputEleme
 const romFileButton = document.querySelector('#rom-file-button') as HTMLButtonElement;
 const targetSelect = document.querySelector('#target-select') as HTMLSelectElement;
 const clockInput = document.querySelector('#clock-input') as HTMLInputElement;
+const persistenceSelect = document.querySelector('#persistence-select') as HTMLSelectElement;
+const volumeInput = document.querySelector('#volume-input') as HTMLInputElement;
+const lowPassSelect = document.querySelector('#low-pass-select') as HTMLSelectElement;
 const resetButton = document.querySelector("#reset-button") as HTMLButtonElement;
+const recordAudioButton = document.querySelector("#record-audio-button") as HTMLButtonElement;
+const recordVideoButton = document.querySelector("#record-video-button") as HTMLButtonElement;
 const keyElements = [...document.querySelectorAll<HTMLDivElement>('.keypad-key')]
     .sort((a, b) => Number.parseInt(`0x${a.innerText}`, 16) - Number.parseInt(`0x${b.innerText}`, 16));
 const keys = keyElements.map((el) => el.getAttribute('key'));
@@ -34,11 +40,16 @@ const getSyncMode = () => Number.parseInt(syncModeSelect.value);
 const getRom = async () => roms[romSelect.value] ?? new Uint8Array(await (await fetch(romSelect.value)).arrayBuffer());
 const getTarget = () => Number.parseInt(targetSelect.value);
 const getClock = () => Number.parseInt(clockInput.value);
+const getPersistence = () => Number.parseInt(persistenceSelect.value);
+const getVolume = () => Number.parseFloat(volumeInput.value);
+const getLowPass = () => Number.parseFloat(lowPassSelect.value);
 
 let _api: JsApi;
 async function getAPI() {
     if (!_api) {
-        _api = new JsApi(getTarget(), getClock(), await getRom(), new Keymap(keys), getSyncMode());
+        _api = new JsApi(getTarget(), Math.round(getClock() / 60), await getRom(), new Keymap(keys), getSyncMode());
+        // The constructor takes instructions per frame, set the clock in Hz like onReset does so it follows the frame rate
+        _api.set_clock_hz(getClock());
     }
     return _api;
 };
@@ -56,7 +67,10 @@ export async function setupUI() {
         api.set_sync_mode(getSyncMode());
         api.load_rom(await getRom());
         api.set_target(getTarget());
-        api.set_clock(getClock());
+        api.set_clock_hz(getClock());
+        api.set_persistence(getPersistence());
+        api.set_volume(getVolume());
+        api.set_low_pass(getLowPass());
         api.reset();
     };
 
@@ -109,15 +123,56 @@ export async function setupUI() {
         romSelect.value = file.name;
     });
 
-    // Apply recommended clock rate to clock input when changing target value
+    // Apply recommended clock rate to clock input when changing target value, targets without one keep the current clock
     targetSelect.addEventListener('change', (e) => {
         let target = e.target as HTMLSelectElement;
         let option = target.querySelector(`option[value="${target.value}"]`) as HTMLOptionElement;
-        clockInput.value = option.getAttribute('clock') ?? '2966';
+        clockInput.value = option.getAttribute('clock') ?? clockInput.value;
     });
 
+    // The clock can be changed while a game is running
+    clockInput.addEventListener('change', () => api.set_clock_hz(getClock()));
+
+    // The audio settings apply right away too
+    volumeInput.addEventListener('input', () => api.set_volume(getVolume()));
+    lowPassSelect.addEventListener('change', () => api.set_low_pass(getLowPass()));
+
     // Reset button
     resetButton.addEventListener('click', onReset);
+
+    // Record audio button, stopping the recording downloads it as a WAV file
+    let recordingAudio = false;
+    recordAudioButton.addEventListener('click', () => {
+        recordingAudio = !recordingAudio;
+        if (recordingAudio) {
+            api.start_audio_recording();
+            recordAudioButton.innerText = 'Stop recording';
+            return;
+        }
+        download(new Blob([api.stop_audio_recording()], { type: 'audio/wav' }), 'chippy.wav');
+        recordAudioButton.innerText = 'Record audio';
+    });
+
+    // Record GIF button, stopping the recording downloads it
+    let recordingVideo = false;
+    recordVideoButton.addEventListener('click', () => {
+        recordingVideo = !recordingVideo;
+        if (recordingVideo) {
+            api.start_video_recording(VideoFormat.Gif);
+            recordVideoButton.innerText = 'Stop recording';
+            return;
+        }
+        download(new Blob([api.stop_video_recording()], { type: 'image/gif' }), 'chippy.gif');
+        recordVideoButton.innerText = 'Record GIF';
+    });
+}
+
+function download(blob: Blob, filename: string) {
+    let link = document.createElement('a');
+    link.href = URL.createObjectURL(blob);
+    link.download = filename;
+    link.click();
+    setTimeout(() => URL.revokeObjectURL(link.href));
 }
 
 export async function waitForCanvas() {